@@ -1,3 +1,16 @@
+pub mod block_rule;
+pub mod current_vote;
+pub mod dedication;
+pub mod event;
 pub mod music;
+pub mod nickname;
+pub mod played;
+pub mod played_request;
+pub mod poll;
+pub mod poll_option;
+pub mod poll_vote;
+pub mod request;
 pub mod room;
+pub mod room_settings;
+pub mod signal;
 pub mod vote;
@@ -11,6 +11,8 @@ pub struct Model {
     pub artist: String,
     pub preview_url: Option<String>,
     pub image_hash: Option<String>,
+    /// The lyrics are explicit, unknown if `None`
+    pub explicit: Option<bool>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
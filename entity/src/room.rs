@@ -10,10 +10,26 @@ pub struct Model {
     pub public_id: u32,
     pub creation_date: DateTimeUtc,
     pub expiration_date: DateTimeUtc,
-    pub user_count: u32,
+    pub join_count: u32,
+    pub event_id: Option<u32>,
+    pub now_playing: Option<i64>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
-pub enum Relation {}
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::event::Entity",
+        from = "Column::EventId",
+        to = "super::event::Column::PublicId"
+    )]
+    Event,
+}
+
+// `Related` trait has to be implemented by hand
+impl Related<super::event::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Event.def()
+    }
+}
 
 impl ActiveModelBehavior for ActiveModel {}
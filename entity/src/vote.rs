@@ -1,6 +1,7 @@
 //! `SeaORM` Entity. Generated by sea-orm-codegen 0.11.0
 
 use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq)]
 #[sea_orm(table_name = "vote")]
@@ -11,7 +12,19 @@ pub struct Model {
     pub room_id: u32,
     pub music_id: i64,
     pub vote_date: DateTimeUtc,
-    pub like: bool,
+    pub value: VoteValue,
+}
+
+/// The vote of a user for a music, the last one replacing the previous ones.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "i32", db_type = "Integer")]
+#[serde(rename_all = "snake_case")]
+#[repr(i32)]
+pub enum VoteValue {
+    Like = 1,
+    /// The vote was retracted
+    Neutral = 0,
+    Dislike = -1,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
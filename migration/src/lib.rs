@@ -1,6 +1,21 @@
 use sea_orm_migration::prelude::*;
 
 mod m20220101_000001_create_table;
+mod m20230601_000002_create_event;
+mod m20230601_000003_room_presence;
+mod m20230601_000004_create_room_settings;
+mod m20230601_000005_vote_value;
+mod m20230601_000006_room_ranking;
+mod m20230601_000007_create_played;
+mod m20230601_000008_create_request;
+mod m20230601_000009_create_block_rule;
+mod m20230601_000010_music_explicit;
+mod m20230601_000011_create_current_vote;
+mod m20230601_000012_create_dedication;
+mod m20230601_000013_create_signal;
+mod m20230601_000014_create_poll;
+mod m20230601_000015_create_nickname;
+mod m20230601_000016_create_played_request;
 
 pub use sea_orm_migration::prelude::MigratorTrait;
 
@@ -9,6 +24,23 @@ pub struct Migrator;
 #[async_trait::async_trait]
 impl MigratorTrait for Migrator {
     fn migrations() -> Vec<Box<dyn MigrationTrait>> {
-        vec![Box::new(m20220101_000001_create_table::Migration)]
+        vec![
+            Box::new(m20220101_000001_create_table::Migration),
+            Box::new(m20230601_000002_create_event::Migration),
+            Box::new(m20230601_000003_room_presence::Migration),
+            Box::new(m20230601_000004_create_room_settings::Migration),
+            Box::new(m20230601_000005_vote_value::Migration),
+            Box::new(m20230601_000006_room_ranking::Migration),
+            Box::new(m20230601_000007_create_played::Migration),
+            Box::new(m20230601_000008_create_request::Migration),
+            Box::new(m20230601_000009_create_block_rule::Migration),
+            Box::new(m20230601_000010_music_explicit::Migration),
+            Box::new(m20230601_000011_create_current_vote::Migration),
+            Box::new(m20230601_000012_create_dedication::Migration),
+            Box::new(m20230601_000013_create_signal::Migration),
+            Box::new(m20230601_000014_create_poll::Migration),
+            Box::new(m20230601_000015_create_nickname::Migration),
+            Box::new(m20230601_000016_create_played_request::Migration),
+        ]
     }
 }
@@ -0,0 +1,92 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(Iden)]
+enum Event {
+    Table,
+    Id,
+    PublicId,
+    Name,
+    CreationDate,
+    ExpirationDate,
+    UserCount,
+}
+
+#[derive(Iden)]
+enum Room {
+    Table,
+    EventId,
+}
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Event::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Event::Id)
+                            .unsigned()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(Event::PublicId)
+                            .unsigned()
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(ColumnDef::new(Event::Name).text().not_null())
+                    .col(
+                        ColumnDef::new(Event::CreationDate)
+                            .date_time()
+                            .not_null()
+                            .default(Keyword::CurrentTimestamp),
+                    )
+                    .col(ColumnDef::new(Event::ExpirationDate).date_time().not_null())
+                    .col(
+                        ColumnDef::new(Event::UserCount)
+                            .unsigned()
+                            .not_null()
+                            .default(Value::Int(Some(0))),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // SQLite cannot add a foreign key to an existing table,
+        // the rooms are detached by hand when an event is deleted.
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Room::Table)
+                    .add_column(ColumnDef::new(Room::EventId).unsigned())
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Room::Table)
+                    .drop_column(Room::EventId)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(Event::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,67 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(Iden)]
+enum Room {
+    Table,
+    UserCount,
+    JoinCount,
+}
+
+#[derive(Iden)]
+enum Event {
+    Table,
+    UserCount,
+    JoinCount,
+}
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+/// The user count was only incremented on join,
+/// the active users are now tracked in memory and the column is kept as a join counter.
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Room::Table)
+                    .rename_column(Room::UserCount, Room::JoinCount)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Event::Table)
+                    .rename_column(Event::UserCount, Event::JoinCount)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Room::Table)
+                    .rename_column(Room::JoinCount, Room::UserCount)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Event::Table)
+                    .rename_column(Event::JoinCount, Event::UserCount)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,80 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(Iden)]
+enum RoomSettings {
+    Table,
+    RoomId,
+    AllowDislikes,
+    MaxUpvotes,
+    VoteCooldown,
+    RankingSize,
+    SearchOpen,
+}
+
+#[derive(Iden)]
+enum Room {
+    Table,
+    PublicId,
+}
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(RoomSettings::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(RoomSettings::RoomId)
+                            .unsigned()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from_tbl(RoomSettings::Table)
+                            .from_col(RoomSettings::RoomId)
+                            .to_tbl(Room::Table)
+                            .to_col(Room::PublicId)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .col(
+                        ColumnDef::new(RoomSettings::AllowDislikes)
+                            .boolean()
+                            .not_null()
+                            .default(Value::Bool(Some(true))),
+                    )
+                    .col(ColumnDef::new(RoomSettings::MaxUpvotes).unsigned())
+                    .col(
+                        ColumnDef::new(RoomSettings::VoteCooldown)
+                            .unsigned()
+                            .not_null()
+                            .default(Value::Int(Some(0))),
+                    )
+                    .col(
+                        ColumnDef::new(RoomSettings::RankingSize)
+                            .unsigned()
+                            .not_null()
+                            .default(Value::Int(Some(10))),
+                    )
+                    .col(
+                        ColumnDef::new(RoomSettings::SearchOpen)
+                            .boolean()
+                            .not_null()
+                            .default(Value::Bool(Some(true))),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(RoomSettings::Table).to_owned())
+            .await
+    }
+}
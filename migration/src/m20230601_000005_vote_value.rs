@@ -0,0 +1,87 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(Iden)]
+enum Vote {
+    Table,
+    Like,
+    Value,
+}
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+/// A vote was either a like or a dislike,
+/// it is now a value: 1 for a like, -1 for a dislike and 0 once retracted.
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Vote::Table)
+                    .add_column(ColumnDef::new(Vote::Value).integer().not_null().default(1))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .exec_stmt(
+                Query::update()
+                    .table(Vote::Table)
+                    .value(
+                        Vote::Value,
+                        Expr::case(Expr::col(Vote::Like).eq(true), 1).finally(-1),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Vote::Table)
+                    .drop_column(Vote::Like)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Vote::Table)
+                    .add_column(
+                        ColumnDef::new(Vote::Like)
+                            .boolean()
+                            .not_null()
+                            .default(true),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // The retracted votes become dislikes, they did not count in the ranking either
+        manager
+            .exec_stmt(
+                Query::update()
+                    .table(Vote::Table)
+                    .value(Vote::Like, Expr::col(Vote::Value).eq(1))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Vote::Table)
+                    .drop_column(Vote::Value)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
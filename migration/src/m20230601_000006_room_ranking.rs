@@ -0,0 +1,40 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(Iden)]
+enum RoomSettings {
+    Table,
+    Ranking,
+}
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(RoomSettings::Table)
+                    .add_column(
+                        ColumnDef::new(RoomSettings::Ranking)
+                            .string_len(16)
+                            .not_null()
+                            .default("sum"),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(RoomSettings::Table)
+                    .drop_column(RoomSettings::Ranking)
+                    .to_owned(),
+            )
+            .await
+    }
+}
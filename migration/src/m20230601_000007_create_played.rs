@@ -0,0 +1,112 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(Iden)]
+enum Played {
+    Table,
+    Id,
+    RoomId,
+    MusicId,
+    PlayedAt,
+}
+
+#[derive(Iden)]
+enum Room {
+    Table,
+    PublicId,
+    NowPlaying,
+}
+
+#[derive(Iden)]
+enum Music {
+    Table,
+    Id,
+}
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Played::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Played::Id)
+                            .unsigned()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Played::RoomId).unsigned().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from_tbl(Played::Table)
+                            .from_col(Played::RoomId)
+                            .to_tbl(Room::Table)
+                            .to_col(Room::PublicId)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .col(ColumnDef::new(Played::MusicId).unsigned().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from_tbl(Played::Table)
+                            .from_col(Played::MusicId)
+                            .to_tbl(Music::Table)
+                            .to_col(Music::Id),
+                    )
+                    .col(
+                        ColumnDef::new(Played::PlayedAt)
+                            .date_time()
+                            .not_null()
+                            .default(Keyword::CurrentTimestamp),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // A music is played once per room, playing it again updates the date
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name("played_room_music_id")
+                    .table(Played::Table)
+                    .col(Played::RoomId)
+                    .col(Played::MusicId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        // SQLite cannot add a foreign key to an existing table,
+        // the music always exists as it is marked as played first.
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Room::Table)
+                    .add_column(ColumnDef::new(Room::NowPlaying).unsigned())
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Room::Table)
+                    .drop_column(Room::NowPlaying)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(Played::Table).to_owned())
+            .await
+    }
+}
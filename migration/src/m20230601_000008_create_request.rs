@@ -0,0 +1,98 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(Iden)]
+enum Request {
+    Table,
+    Id,
+    RoomId,
+    MusicId,
+    Decision,
+    Reason,
+    Position,
+    UpdatedAt,
+}
+
+#[derive(Iden)]
+enum Room {
+    Table,
+    PublicId,
+}
+
+#[derive(Iden)]
+enum Music {
+    Table,
+    Id,
+}
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+/// The decisions of the DJ about the requested musics,
+/// a music without decision is pending.
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Request::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Request::Id)
+                            .unsigned()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Request::RoomId).unsigned().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from_tbl(Request::Table)
+                            .from_col(Request::RoomId)
+                            .to_tbl(Room::Table)
+                            .to_col(Room::PublicId)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .col(ColumnDef::new(Request::MusicId).unsigned().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from_tbl(Request::Table)
+                            .from_col(Request::MusicId)
+                            .to_tbl(Music::Table)
+                            .to_col(Music::Id),
+                    )
+                    .col(ColumnDef::new(Request::Decision).string_len(16).not_null())
+                    .col(ColumnDef::new(Request::Reason).text())
+                    .col(ColumnDef::new(Request::Position).unsigned())
+                    .col(
+                        ColumnDef::new(Request::UpdatedAt)
+                            .date_time()
+                            .not_null()
+                            .default(Keyword::CurrentTimestamp),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name("request_room_music_id")
+                    .table(Request::Table)
+                    .col(Request::RoomId)
+                    .col(Request::MusicId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Request::Table).to_owned())
+            .await
+    }
+}
@@ -0,0 +1,65 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(Iden)]
+enum BlockRule {
+    Table,
+    Id,
+    RoomId,
+    Kind,
+    Value,
+    CreationDate,
+}
+
+#[derive(Iden)]
+enum Room {
+    Table,
+    PublicId,
+}
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(BlockRule::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(BlockRule::Id)
+                            .unsigned()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    // The global rules have no room
+                    .col(ColumnDef::new(BlockRule::RoomId).unsigned())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from_tbl(BlockRule::Table)
+                            .from_col(BlockRule::RoomId)
+                            .to_tbl(Room::Table)
+                            .to_col(Room::PublicId)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .col(ColumnDef::new(BlockRule::Kind).string_len(16).not_null())
+                    .col(ColumnDef::new(BlockRule::Value).text().not_null())
+                    .col(
+                        ColumnDef::new(BlockRule::CreationDate)
+                            .date_time()
+                            .not_null()
+                            .default(Keyword::CurrentTimestamp),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(BlockRule::Table).to_owned())
+            .await
+    }
+}
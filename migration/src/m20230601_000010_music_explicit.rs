@@ -0,0 +1,70 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(Iden)]
+enum Music {
+    Table,
+    Explicit,
+}
+
+#[derive(Iden)]
+enum RoomSettings {
+    Table,
+    AllowExplicit,
+}
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+/// The explicit flag of the existing musics is unknown (null),
+/// it is fetched from Deezer by the server.
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Music::Table)
+                    .add_column(ColumnDef::new(Music::Explicit).boolean())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(RoomSettings::Table)
+                    .add_column(
+                        ColumnDef::new(RoomSettings::AllowExplicit)
+                            .boolean()
+                            .not_null()
+                            .default(Value::Bool(Some(true))),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(RoomSettings::Table)
+                    .drop_column(RoomSettings::AllowExplicit)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Music::Table)
+                    .drop_column(Music::Explicit)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,115 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(Iden)]
+enum CurrentVote {
+    Table,
+    RoomId,
+    UserToken,
+    MusicId,
+    Value,
+    VoteDate,
+}
+
+#[derive(Iden)]
+enum Vote {
+    Table,
+    RoomId,
+    UserToken,
+    MusicId,
+    Value,
+    VoteDate,
+}
+
+#[derive(Iden)]
+enum Room {
+    Table,
+    PublicId,
+}
+
+#[derive(Iden)]
+enum Music {
+    Table,
+    Id,
+}
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+/// The current vote of each user is stored in its own table, updated in place,
+/// the `vote` table being kept as the log of every vote cast.
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(CurrentVote::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(CurrentVote::RoomId).unsigned().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from_tbl(CurrentVote::Table)
+                            .from_col(CurrentVote::RoomId)
+                            .to_tbl(Room::Table)
+                            .to_col(Room::PublicId)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .col(ColumnDef::new(CurrentVote::UserToken).uuid().not_null())
+                    .col(ColumnDef::new(CurrentVote::MusicId).unsigned().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from_tbl(CurrentVote::Table)
+                            .from_col(CurrentVote::MusicId)
+                            .to_tbl(Music::Table)
+                            .to_col(Music::Id),
+                    )
+                    .col(ColumnDef::new(CurrentVote::Value).integer().not_null())
+                    .col(
+                        ColumnDef::new(CurrentVote::VoteDate)
+                            .date_time()
+                            .not_null()
+                            .default(Keyword::CurrentTimestamp),
+                    )
+                    // A user has a single vote per music of a room
+                    .primary_key(
+                        Index::create()
+                            .col(CurrentVote::RoomId)
+                            .col(CurrentVote::UserToken)
+                            .col(CurrentVote::MusicId),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // The current vote is the last one of the log
+        manager
+            .exec_stmt(
+                Query::insert()
+                    .into_table(CurrentVote::Table)
+                    .columns([
+                        CurrentVote::RoomId,
+                        CurrentVote::UserToken,
+                        CurrentVote::MusicId,
+                        CurrentVote::Value,
+                        CurrentVote::VoteDate,
+                    ])
+                    .select_from(
+                        Query::select()
+                            .columns([Vote::RoomId, Vote::UserToken, Vote::MusicId, Vote::Value])
+                            .expr(Func::max(Expr::col(Vote::VoteDate)))
+                            .from(Vote::Table)
+                            .group_by_columns([Vote::RoomId, Vote::UserToken, Vote::MusicId])
+                            .to_owned(),
+                    )
+                    .map_err(|err| DbErr::Migration(err.to_string()))?
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(CurrentVote::Table).to_owned())
+            .await
+    }
+}
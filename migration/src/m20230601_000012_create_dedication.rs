@@ -0,0 +1,99 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(Iden)]
+enum Dedication {
+    Table,
+    Id,
+    RoomId,
+    UserToken,
+    MusicId,
+    Message,
+    Status,
+    CreationDate,
+}
+
+#[derive(Iden)]
+enum Room {
+    Table,
+    PublicId,
+}
+
+#[derive(Iden)]
+enum Music {
+    Table,
+    Id,
+}
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Dedication::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Dedication::Id)
+                            .unsigned()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Dedication::RoomId).unsigned().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from_tbl(Dedication::Table)
+                            .from_col(Dedication::RoomId)
+                            .to_tbl(Room::Table)
+                            .to_col(Room::PublicId)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .col(ColumnDef::new(Dedication::UserToken).uuid().not_null())
+                    .col(ColumnDef::new(Dedication::MusicId).unsigned().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from_tbl(Dedication::Table)
+                            .from_col(Dedication::MusicId)
+                            .to_tbl(Music::Table)
+                            .to_col(Music::Id),
+                    )
+                    .col(ColumnDef::new(Dedication::Message).text().not_null())
+                    .col(
+                        ColumnDef::new(Dedication::Status)
+                            .string_len(16)
+                            .not_null()
+                            .default("pending"),
+                    )
+                    .col(
+                        ColumnDef::new(Dedication::CreationDate)
+                            .date_time()
+                            .not_null()
+                            .default(Keyword::CurrentTimestamp),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // The moderation queue lists the dedications of a room by status
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name("dedication_room_status")
+                    .table(Dedication::Table)
+                    .col(Dedication::RoomId)
+                    .col(Dedication::Status)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Dedication::Table).to_owned())
+            .await
+    }
+}
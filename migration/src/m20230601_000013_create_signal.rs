@@ -0,0 +1,77 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(Iden)]
+enum Signal {
+    Table,
+    Id,
+    RoomId,
+    UserToken,
+    Kind,
+    SignalDate,
+}
+
+#[derive(Iden)]
+enum Room {
+    Table,
+    PublicId,
+}
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Signal::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Signal::Id)
+                            .unsigned()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Signal::RoomId).unsigned().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from_tbl(Signal::Table)
+                            .from_col(Signal::RoomId)
+                            .to_tbl(Room::Table)
+                            .to_col(Room::PublicId)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .col(ColumnDef::new(Signal::UserToken).uuid().not_null())
+                    .col(ColumnDef::new(Signal::Kind).string_len(16).not_null())
+                    .col(
+                        ColumnDef::new(Signal::SignalDate)
+                            .date_time()
+                            .not_null()
+                            .default(Keyword::CurrentTimestamp),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // The meter and its history read the signals of a room by date
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name("signal_room_date")
+                    .table(Signal::Table)
+                    .col(Signal::RoomId)
+                    .col(Signal::SignalDate)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Signal::Table).to_owned())
+            .await
+    }
+}
@@ -0,0 +1,171 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(Iden)]
+enum Poll {
+    Table,
+    Id,
+    RoomId,
+    CreationDate,
+    ClosesAt,
+    Closed,
+    Winner,
+}
+
+#[derive(Iden)]
+enum PollOption {
+    Table,
+    PollId,
+    MusicId,
+    Position,
+}
+
+#[derive(Iden)]
+enum PollVote {
+    Table,
+    PollId,
+    UserToken,
+    MusicId,
+    VoteDate,
+}
+
+#[derive(Iden)]
+enum Room {
+    Table,
+    PublicId,
+}
+
+#[derive(Iden)]
+enum Music {
+    Table,
+    Id,
+}
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Poll::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Poll::Id)
+                            .unsigned()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Poll::RoomId).unsigned().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from_tbl(Poll::Table)
+                            .from_col(Poll::RoomId)
+                            .to_tbl(Room::Table)
+                            .to_col(Room::PublicId)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .col(
+                        ColumnDef::new(Poll::CreationDate)
+                            .date_time()
+                            .not_null()
+                            .default(Keyword::CurrentTimestamp),
+                    )
+                    .col(ColumnDef::new(Poll::ClosesAt).date_time().not_null())
+                    .col(
+                        ColumnDef::new(Poll::Closed)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .col(ColumnDef::new(Poll::Winner).unsigned())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from_tbl(Poll::Table)
+                            .from_col(Poll::Winner)
+                            .to_tbl(Music::Table)
+                            .to_col(Music::Id),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(PollOption::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(PollOption::PollId).unsigned().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from_tbl(PollOption::Table)
+                            .from_col(PollOption::PollId)
+                            .to_tbl(Poll::Table)
+                            .to_col(Poll::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .col(ColumnDef::new(PollOption::MusicId).unsigned().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from_tbl(PollOption::Table)
+                            .from_col(PollOption::MusicId)
+                            .to_tbl(Music::Table)
+                            .to_col(Music::Id),
+                    )
+                    .col(ColumnDef::new(PollOption::Position).unsigned().not_null())
+                    .primary_key(
+                        Index::create()
+                            .col(PollOption::PollId)
+                            .col(PollOption::MusicId),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(PollVote::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(PollVote::PollId).unsigned().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from_tbl(PollVote::Table)
+                            .from_col(PollVote::PollId)
+                            .to_tbl(Poll::Table)
+                            .to_col(Poll::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .col(ColumnDef::new(PollVote::UserToken).uuid().not_null())
+                    .col(ColumnDef::new(PollVote::MusicId).unsigned().not_null())
+                    .col(
+                        ColumnDef::new(PollVote::VoteDate)
+                            .date_time()
+                            .not_null()
+                            .default(Keyword::CurrentTimestamp),
+                    )
+                    // A user votes once per poll
+                    .primary_key(
+                        Index::create()
+                            .col(PollVote::PollId)
+                            .col(PollVote::UserToken),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(PollVote::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(PollOption::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(Poll::Table).to_owned())
+            .await
+    }
+}
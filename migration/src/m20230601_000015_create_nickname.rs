@@ -0,0 +1,77 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(Iden)]
+enum Nickname {
+    Table,
+    RoomId,
+    UserToken,
+    Nickname,
+    NameKey,
+    CreationDate,
+}
+
+#[derive(Iden)]
+enum Room {
+    Table,
+    PublicId,
+}
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Nickname::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(Nickname::RoomId).unsigned().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from_tbl(Nickname::Table)
+                            .from_col(Nickname::RoomId)
+                            .to_tbl(Room::Table)
+                            .to_col(Room::PublicId)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .col(ColumnDef::new(Nickname::UserToken).uuid().not_null())
+                    .col(ColumnDef::new(Nickname::Nickname).string_len(32).not_null())
+                    .col(ColumnDef::new(Nickname::NameKey).string_len(32).not_null())
+                    .col(
+                        ColumnDef::new(Nickname::CreationDate)
+                            .date_time()
+                            .not_null()
+                            .default(Keyword::CurrentTimestamp),
+                    )
+                    .primary_key(
+                        Index::create()
+                            .col(Nickname::RoomId)
+                            .col(Nickname::UserToken),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Two users of a room cannot have the same nickname, ignoring the case
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name("nickname_room_name_key")
+                    .table(Nickname::Table)
+                    .col(Nickname::RoomId)
+                    .col(Nickname::NameKey)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Nickname::Table).to_owned())
+            .await
+    }
+}
@@ -0,0 +1,127 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(Iden)]
+enum PlayedRequest {
+    Table,
+    RoomId,
+    MusicId,
+    UserToken,
+}
+
+#[derive(Iden)]
+enum CurrentVote {
+    Table,
+    RoomId,
+    UserToken,
+    MusicId,
+    Value,
+    VoteDate,
+}
+
+#[derive(Iden)]
+enum Played {
+    Table,
+    RoomId,
+    MusicId,
+    PlayedAt,
+}
+
+#[derive(Iden)]
+enum Room {
+    Table,
+    PublicId,
+}
+
+#[derive(Iden)]
+enum Music {
+    Table,
+    Id,
+}
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+/// The users who liked a music when it was played are kept,
+/// as their current vote can change afterwards.
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(PlayedRequest::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(PlayedRequest::RoomId).unsigned().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from_tbl(PlayedRequest::Table)
+                            .from_col(PlayedRequest::RoomId)
+                            .to_tbl(Room::Table)
+                            .to_col(Room::PublicId)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .col(ColumnDef::new(PlayedRequest::MusicId).unsigned().not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from_tbl(PlayedRequest::Table)
+                            .from_col(PlayedRequest::MusicId)
+                            .to_tbl(Music::Table)
+                            .to_col(Music::Id),
+                    )
+                    .col(ColumnDef::new(PlayedRequest::UserToken).uuid().not_null())
+                    .primary_key(
+                        Index::create()
+                            .col(PlayedRequest::RoomId)
+                            .col(PlayedRequest::MusicId)
+                            .col(PlayedRequest::UserToken),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // The likes of the musics already played are the current ones cast before the music played
+        manager
+            .exec_stmt(
+                Query::insert()
+                    .into_table(PlayedRequest::Table)
+                    .columns([
+                        PlayedRequest::RoomId,
+                        PlayedRequest::MusicId,
+                        PlayedRequest::UserToken,
+                    ])
+                    .select_from(
+                        Query::select()
+                            .columns([
+                                (CurrentVote::Table, CurrentVote::RoomId),
+                                (CurrentVote::Table, CurrentVote::MusicId),
+                                (CurrentVote::Table, CurrentVote::UserToken),
+                            ])
+                            .from(CurrentVote::Table)
+                            .inner_join(
+                                Played::Table,
+                                Expr::col((Played::Table, Played::RoomId))
+                                    .equals((CurrentVote::Table, CurrentVote::RoomId))
+                                    .and(
+                                        Expr::col((Played::Table, Played::MusicId))
+                                            .equals((CurrentVote::Table, CurrentVote::MusicId)),
+                                    ),
+                            )
+                            .and_where(Expr::col((CurrentVote::Table, CurrentVote::Value)).eq(1))
+                            .and_where(
+                                Expr::col((CurrentVote::Table, CurrentVote::VoteDate))
+                                    .lte(Expr::col((Played::Table, Played::PlayedAt))),
+                            )
+                            .to_owned(),
+                    )
+                    .map_err(|err| DbErr::Migration(err.to_string()))?
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(PlayedRequest::Table).to_owned())
+            .await
+    }
+}
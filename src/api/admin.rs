@@ -16,7 +16,7 @@ use crate::utils::{
     room_id::RoomID,
 };
 
-use super::state::ApiState;
+use super::state::{ApiState, Presence};
 
 #[derive(Debug, Deserialize)]
 pub struct LoginBody {
@@ -66,16 +66,21 @@ pub struct GetRoom {
     id: RoomID,
     creation: DateTime<Utc>,
     expiration: DateTime<Utc>,
-    user_count: u32,
+    join_count: u32,
+    active_users: usize,
+    event: Option<RoomID>,
 }
 
-impl From<room::Model> for GetRoom {
-    fn from(model: room::Model) -> Self {
+impl GetRoom {
+    pub fn new(model: room::Model, presence: &Presence) -> Self {
+        let id = RoomID::new(model.public_id);
         Self {
-            id: RoomID::new(model.public_id),
+            id,
             creation: model.creation_date,
             expiration: model.expiration_date,
-            user_count: model.user_count,
+            join_count: model.join_count,
+            active_users: presence.active_users(id),
+            event: model.event_id.map(RoomID::new),
         }
     }
 }
@@ -93,7 +98,10 @@ pub async fn get_rooms(
 
     let rooms = room::Entity::find().all(&state.db).await?;
 
-    let rooms = rooms.into_iter().map(GetRoom::from).collect();
+    let rooms = rooms
+        .into_iter()
+        .map(|room| GetRoom::new(room, &state.presence))
+        .collect();
 
     Ok(Json(rooms))
 }
@@ -102,6 +110,8 @@ pub async fn get_rooms(
 pub struct CreateRoom {
     id: RoomID,
     expiration: DateTime<Utc>,
+    #[serde(default)]
+    event: Option<RoomID>,
 }
 
 impl CreateRoom {
@@ -109,6 +119,7 @@ impl CreateRoom {
         room::ActiveModel {
             public_id: Set(self.id.value()),
             expiration_date: Set(self.expiration),
+            event_id: Set(self.event.map(|event| event.value())),
             ..Default::default()
         }
     }
@@ -119,6 +130,9 @@ pub enum CreateRoomsError {
     /// Room id already exists
     #[status(StatusCode::CONFLICT)]
     RoomIdAlreadyExists,
+    /// Event not found
+    #[status(StatusCode::BAD_REQUEST)]
+    EventNotFound,
 }
 
 pub async fn create_room(
@@ -130,24 +144,38 @@ pub async fn create_room(
         return Err(CreateRoomsError::Unauthorized);
     }
 
+    if let Some(event_id) = room.event {
+        event::Entity::find()
+            .filter(event::Column::PublicId.eq(event_id.value()))
+            .one(&state.db)
+            .await?
+            .ok_or(CreateRoomsError::EventNotFound)?;
+    }
+
     // Create the room in the database
     room.to_active_model()
         .save(&state.db)
         .await
         .and_then(room::ActiveModel::try_into_model)
-        .map(GetRoom::from)
+        .map(|room| GetRoom::new(room, &state.presence))
         .map(Json)
         .map_err(|err| {
-            // Ugly line to get the error code from sqlx and check if it's a duplicate key error
-            if let DbErr::Exec(RuntimeErr::SqlxError(err)) = &err {
-                if err.as_database_error().and_then(|e| e.code()).as_deref() == Some("2067") {
-                    return CreateRoomsError::RoomIdAlreadyExists;
-                }
+            if is_duplicate_key(&err) {
+                return CreateRoomsError::RoomIdAlreadyExists;
             }
             CreateRoomsError::InternalError(err)
         })
 }
 
+/// Check if the error is a unique constraint violation.
+pub(super) fn is_duplicate_key(err: &DbErr) -> bool {
+    // Ugly line to get the error code from sqlx and check if it's a duplicate key error
+    if let DbErr::Exec(RuntimeErr::SqlxError(err)) = err {
+        return err.as_database_error().and_then(|e| e.code()).as_deref() == Some("2067");
+    }
+    false
+}
+
 #[api_macro::error(internal_error, unauthorized)]
 pub enum DeleteRoomsError {
     /// Room id does not exist
@@ -0,0 +1,205 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use entity::{block_rule::BlockKind, *};
+use sea_orm::{prelude::*, Condition, QueryOrder, Set};
+
+use crate::utils::{
+    blocklist::Blocklist,
+    jwt::{Role, User},
+    room_id::RoomID,
+};
+
+use super::state::ApiState;
+
+/// Get the rules of the room and the global rules.
+pub(super) async fn room_blocklist(
+    db: &DatabaseConnection,
+    room_id: RoomID,
+) -> Result<Blocklist, DbErr> {
+    let rules = block_rule::Entity::find()
+        .filter(
+            Condition::any()
+                .add(block_rule::Column::RoomId.eq(room_id.value()))
+                .add(block_rule::Column::RoomId.is_null()),
+        )
+        .all(db)
+        .await?;
+    Ok(Blocklist::new(rules))
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct BlockRule {
+    id: u32,
+    /// The room of the rule, or `None` for every room
+    room: Option<RoomID>,
+    kind: BlockKind,
+    value: String,
+    creation: DateTime<Utc>,
+}
+
+impl From<block_rule::Model> for BlockRule {
+    fn from(model: block_rule::Model) -> Self {
+        Self {
+            id: model.id,
+            room: model.room_id.map(RoomID::new),
+            kind: model.kind,
+            value: model.value,
+            creation: model.creation_date,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CreateBlockRule {
+    kind: BlockKind,
+    value: String,
+}
+
+impl CreateBlockRule {
+    const MAX_VALUE_LENGTH: usize = 200;
+
+    fn is_valid(&self) -> bool {
+        let value = self.value.trim();
+        let valid_value = match self.kind {
+            BlockKind::Track => value.parse::<i64>().is_ok(),
+            BlockKind::Artist | BlockKind::Title | BlockKind::Nickname => !value.is_empty(),
+        };
+        valid_value && value.chars().count() <= Self::MAX_VALUE_LENGTH
+    }
+
+    fn to_active_model(&self, room_id: Option<RoomID>) -> block_rule::ActiveModel {
+        block_rule::ActiveModel {
+            room_id: Set(room_id.map(|room_id| room_id.value())),
+            kind: Set(self.kind),
+            value: Set(self.value.trim().to_string()),
+            ..Default::default()
+        }
+    }
+}
+
+#[api_macro::error(internal_error, unauthorized)]
+pub enum BlocklistError {
+    /// Room not found
+    #[status(StatusCode::NOT_FOUND)]
+    RoomNotFound,
+    /// Rule not found
+    #[status(StatusCode::NOT_FOUND)]
+    RuleNotFound,
+    /// Invalid rule
+    #[status(StatusCode::BAD_REQUEST)]
+    InvalidRule,
+}
+
+/// Get the rules applying to every room.
+pub async fn get_global_rules(
+    State(state): State<ApiState>,
+    user: User,
+) -> Result<Json<Vec<BlockRule>>, BlocklistError> {
+    if user.role != Role::Admin {
+        return Err(BlocklistError::Unauthorized);
+    }
+
+    get_rules(&state.db, None).await
+}
+
+/// Get the rules of the room, without the global ones.
+pub async fn get_room_rules(
+    State(state): State<ApiState>,
+    Path(room_id): Path<RoomID>,
+    user: User,
+) -> Result<Json<Vec<BlockRule>>, BlocklistError> {
+    if user.role != Role::Admin {
+        return Err(BlocklistError::Unauthorized);
+    }
+
+    get_rules(&state.db, Some(room_id)).await
+}
+
+pub async fn create_global_rule(
+    State(state): State<ApiState>,
+    user: User,
+    Json(rule): Json<CreateBlockRule>,
+) -> Result<Json<BlockRule>, BlocklistError> {
+    if user.role != Role::Admin {
+        return Err(BlocklistError::Unauthorized);
+    }
+
+    create_rule(&state.db, None, rule).await
+}
+
+pub async fn create_room_rule(
+    State(state): State<ApiState>,
+    Path(room_id): Path<RoomID>,
+    user: User,
+    Json(rule): Json<CreateBlockRule>,
+) -> Result<Json<BlockRule>, BlocklistError> {
+    if user.role != Role::Admin {
+        return Err(BlocklistError::Unauthorized);
+    }
+
+    room::Entity::find()
+        .filter(room::Column::PublicId.eq(room_id.value()))
+        .one(&state.db)
+        .await?
+        .ok_or(BlocklistError::RoomNotFound)?;
+
+    create_rule(&state.db, Some(room_id), rule).await
+}
+
+pub async fn delete_rule(
+    State(state): State<ApiState>,
+    Path(rule_id): Path<u32>,
+    user: User,
+) -> Result<(), BlocklistError> {
+    if user.role != Role::Admin {
+        return Err(BlocklistError::Unauthorized);
+    }
+
+    let deleted = block_rule::Entity::delete_by_id(rule_id)
+        .exec(&state.db)
+        .await?
+        .rows_affected;
+
+    match deleted {
+        0 => Err(BlocklistError::RuleNotFound),
+        _ => Ok(()),
+    }
+}
+
+async fn get_rules(
+    db: &DatabaseConnection,
+    room_id: Option<RoomID>,
+) -> Result<Json<Vec<BlockRule>>, BlocklistError> {
+    let room_filter = match room_id {
+        Some(room_id) => block_rule::Column::RoomId.eq(room_id.value()),
+        None => block_rule::Column::RoomId.is_null(),
+    };
+
+    let rules = block_rule::Entity::find()
+        .filter(room_filter)
+        .order_by_asc(block_rule::Column::CreationDate)
+        .all(db)
+        .await?;
+
+    Ok(Json(rules.into_iter().map(BlockRule::from).collect()))
+}
+
+async fn create_rule(
+    db: &DatabaseConnection,
+    room_id: Option<RoomID>,
+    rule: CreateBlockRule,
+) -> Result<Json<BlockRule>, BlocklistError> {
+    if !rule.is_valid() {
+        return Err(BlocklistError::InvalidRule);
+    }
+
+    let model = rule.to_active_model(room_id).insert(db).await?;
+
+    Ok(Json(model.into()))
+}
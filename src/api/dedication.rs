@@ -0,0 +1,154 @@
+use axum::{
+    extract::{self, Path, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use entity::{dedication::DedicationStatus, *};
+use sea_orm::{prelude::*, ConnectionTrait, QueryOrder, Set};
+
+use crate::utils::{
+    jwt::{Role, User},
+    room_id::RoomID,
+};
+
+use super::{
+    nickname::{room_nicknames, user_nickname},
+    state::ApiState,
+    websocket::RoomEvent,
+    MusicId,
+};
+
+/// The maximum number of characters of a dedication.
+pub const MAX_MESSAGE_LENGTH: usize = 140;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Dedication {
+    id: u32,
+    music_id: MusicId,
+    message: String,
+    status: DedicationStatus,
+    creation: DateTime<Utc>,
+    /// The user who wrote it
+    user: Uuid,
+    /// The nickname of the user, if they chose one
+    nickname: Option<String>,
+}
+
+impl Dedication {
+    fn new(model: dedication::Model, nickname: Option<String>) -> Self {
+        Self {
+            id: model.id,
+            music_id: model.music_id,
+            message: model.message,
+            status: model.status,
+            creation: model.creation_date,
+            user: model.user_token,
+            nickname,
+        }
+    }
+}
+
+/// Save the dedication of a like, waiting for the DJ approval.
+pub(super) async fn save_dedication(
+    db: &impl ConnectionTrait,
+    room_id: RoomID,
+    user_token: Uuid,
+    music_id: MusicId,
+    message: String,
+) -> Result<Dedication, DbErr> {
+    let model = dedication::ActiveModel {
+        room_id: Set(room_id.value()),
+        user_token: Set(user_token),
+        music_id: Set(music_id),
+        message: Set(message),
+        status: Set(DedicationStatus::Pending),
+        creation_date: Set(Utc::now()),
+        ..Default::default()
+    }
+    .insert(db)
+    .await?;
+    let nickname = user_nickname(db, room_id, user_token).await?;
+    Ok(Dedication::new(model, nickname))
+}
+
+#[api_macro::error(internal_error, unauthorized)]
+pub enum DedicationError {
+    /// Dedication not found
+    #[status(StatusCode::NOT_FOUND)]
+    DedicationNotFound,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct DedicationFilter {
+    /// Only the dedications with this status, all of them if `None`
+    status: Option<DedicationStatus>,
+}
+
+/// Get the dedications of the room, the most recent first.
+pub async fn get_dedications(
+    State(state): State<ApiState>,
+    Path(room_id): Path<RoomID>,
+    extract::Query(filter): extract::Query<DedicationFilter>,
+    user: User,
+) -> Result<Json<Vec<Dedication>>, DedicationError> {
+    if user.role != Role::Admin {
+        return Err(DedicationError::Unauthorized);
+    }
+
+    let mut query = dedication::Entity::find()
+        .filter(dedication::Column::RoomId.eq(room_id.value()))
+        .order_by_desc(dedication::Column::CreationDate);
+    if let Some(status) = filter.status {
+        query = query.filter(dedication::Column::Status.eq(status));
+    }
+
+    let dedications = query.all(&state.db).await?;
+    let nicknames = room_nicknames(&state.db, room_id).await?;
+    Ok(Json(
+        dedications
+            .into_iter()
+            .map(|model| {
+                let nickname = nicknames.get(&model.user_token).cloned();
+                Dedication::new(model, nickname)
+            })
+            .collect(),
+    ))
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ModerateDedication {
+    status: DedicationStatus,
+}
+
+/// Approve or hide a dedication, the DJ receiving the moderated ones on the websocket.
+pub async fn moderate_dedication(
+    State(state): State<ApiState>,
+    Path((room_id, dedication_id)): Path<(RoomID, u32)>,
+    user: User,
+    Json(moderation): Json<ModerateDedication>,
+) -> Result<Json<Dedication>, DedicationError> {
+    if user.role != Role::Admin {
+        return Err(DedicationError::Unauthorized);
+    }
+
+    let model = dedication::Entity::find_by_id(dedication_id)
+        .filter(dedication::Column::RoomId.eq(room_id.value()))
+        .one(&state.db)
+        .await?
+        .ok_or(DedicationError::DedicationNotFound)?;
+
+    let mut model: dedication::ActiveModel = model.into();
+    model.status = Set(moderation.status);
+    let model = model.update(&state.db).await?;
+    let nickname = user_nickname(&state.db, room_id, model.user_token).await?;
+    let dedication = Dedication::new(model, nickname);
+
+    let event = RoomEvent::Dedication(dedication.clone());
+    state.rooms_channels.send(room_id, event);
+
+    Ok(Json(dedication))
+}
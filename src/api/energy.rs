@@ -0,0 +1,184 @@
+use axum::{
+    extract::{self, Path, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::{Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+use entity::{signal::SignalKind, *};
+use sea_orm::{
+    prelude::*,
+    sea_query::{Expr, Query},
+    ConnectionTrait, QueryOrder, QuerySelect,
+};
+
+use crate::utils::{
+    energy::{time_series, EnergyMeter, EnergyPoint},
+    jwt::{Role, User},
+    room_id::RoomID,
+};
+
+use super::{event::has_room_access, state::ApiState, websocket::RoomEvent};
+
+/// The time a user waits between two signals, in seconds.
+const SIGNAL_COOLDOWN: i64 = 30;
+/// The period of the signals counted in the live meter, in minutes.
+const METER_WINDOW: i64 = 5;
+/// The longest history of the meter, in minutes.
+const MAX_HISTORY: u32 = 24 * 60;
+
+#[api_macro::error(internal_error, unauthorized)]
+pub enum EnergyError {
+    /// Wait a bit before sending another signal
+    #[status(StatusCode::TOO_MANY_REQUESTS)]
+    Cooldown,
+    /// The history must be at most 1440 minutes long, with a step of at least 1 minute
+    #[status(StatusCode::BAD_REQUEST)]
+    InvalidHistory,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SignalBody {
+    kind: SignalKind,
+}
+
+/// Tell the DJ what the crowd wants, regardless of the musics.
+pub async fn send_signal(
+    State(state): State<ApiState>,
+    Path(room_id): Path<RoomID>,
+    user: User,
+    Json(body): Json<SignalBody>,
+) -> Result<Json<EnergyMeter>, EnergyError> {
+    if !has_room_access(&state.db, &user, room_id).await? {
+        return Err(EnergyError::Unauthorized);
+    }
+    state.record_activity(room_id, &user);
+
+    // The cooldown is checked by the insertion itself, so two signals at once can't both pass
+    let now = Utc::now();
+    let recent_signal = Query::select()
+        .expr(Expr::val(1))
+        .from(signal::Entity)
+        .and_where(signal::Column::RoomId.eq(room_id.value()))
+        .and_where(signal::Column::UserToken.eq(user.uid))
+        .and_where(signal::Column::SignalDate.gt(now - Duration::seconds(SIGNAL_COOLDOWN)))
+        .take();
+    let values = Query::select()
+        .exprs([
+            Expr::val(room_id.value()),
+            Expr::val(user.uid),
+            Expr::val(body.kind),
+            Expr::val(now),
+        ])
+        .and_where(Expr::exists(recent_signal).not())
+        .take();
+    let insert = Query::insert()
+        .into_table(signal::Entity)
+        .columns([
+            signal::Column::RoomId,
+            signal::Column::UserToken,
+            signal::Column::Kind,
+            signal::Column::SignalDate,
+        ])
+        .select_from(values)
+        .map_err(|e| DbErr::Custom(e.to_string()))?
+        .to_owned();
+
+    let backend = state.db.get_database_backend();
+    let inserted = state.db.execute(backend.build(&insert)).await?;
+    if inserted.rows_affected() == 0 {
+        return Err(EnergyError::Cooldown);
+    }
+
+    let meter = energy_meter(&state.db, room_id).await?;
+    state.rooms_channels.send(room_id, RoomEvent::Energy(meter));
+
+    Ok(Json(meter))
+}
+
+/// Get the signals of the last minutes of the room.
+pub async fn get_energy(
+    State(state): State<ApiState>,
+    Path(room_id): Path<RoomID>,
+    user: User,
+) -> Result<Json<EnergyMeter>, EnergyError> {
+    if !has_room_access(&state.db, &user, room_id).await? {
+        return Err(EnergyError::Unauthorized);
+    }
+    state.record_activity(room_id, &user);
+
+    Ok(Json(energy_meter(&state.db, room_id).await?))
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct HistoryQuery {
+    /// The length of the history in minutes
+    #[serde(default = "default_history_minutes")]
+    minutes: u32,
+    /// The length of each period in minutes
+    #[serde(default = "default_history_step")]
+    step: u32,
+}
+
+fn default_history_minutes() -> u32 {
+    60
+}
+
+fn default_history_step() -> u32 {
+    METER_WINDOW as u32
+}
+
+/// Get the meter of each period of the last minutes, the oldest first.
+pub async fn get_energy_history(
+    State(state): State<ApiState>,
+    Path(room_id): Path<RoomID>,
+    extract::Query(query): extract::Query<HistoryQuery>,
+    user: User,
+) -> Result<Json<Vec<EnergyPoint>>, EnergyError> {
+    if user.role != Role::Admin {
+        return Err(EnergyError::Unauthorized);
+    }
+    if query.minutes == 0 || query.minutes > MAX_HISTORY || query.step == 0 {
+        return Err(EnergyError::InvalidHistory);
+    }
+
+    let end = Utc::now();
+    let start = end - Duration::minutes(query.minutes.into());
+    let signals = room_signals(&state.db, room_id, start).await?;
+
+    Ok(Json(time_series(
+        &signals,
+        start,
+        end,
+        Duration::minutes(query.step.into()),
+    )))
+}
+
+/// The meter of the last minutes of the room.
+pub(super) async fn energy_meter(
+    db: &DatabaseConnection,
+    room_id: RoomID,
+) -> Result<EnergyMeter, DbErr> {
+    let start = Utc::now() - Duration::minutes(METER_WINDOW);
+    let signals = room_signals(db, room_id, start).await?;
+    Ok(EnergyMeter::new(signals.into_iter().map(|(_, kind)| kind)))
+}
+
+/// The signals sent in the room since `start`, the oldest first.
+async fn room_signals(
+    db: &DatabaseConnection,
+    room_id: RoomID,
+    start: DateTimeUtc,
+) -> Result<Vec<(DateTimeUtc, SignalKind)>, DbErr> {
+    signal::Entity::find()
+        .select_only()
+        .column(signal::Column::SignalDate)
+        .column(signal::Column::Kind)
+        .filter(signal::Column::RoomId.eq(room_id.value()))
+        .filter(signal::Column::SignalDate.gte(start))
+        .order_by_asc(signal::Column::SignalDate)
+        .into_tuple()
+        .all(db)
+        .await
+}
@@ -0,0 +1,384 @@
+use std::time::Duration;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::time::sleep;
+
+use entity::*;
+use sea_orm::{prelude::*, sea_query::Expr, ActiveValue::Set, TransactionTrait, TryIntoModel};
+
+use crate::utils::{
+    jwt::{Role, User, UserToken},
+    room_id::RoomID,
+};
+
+use super::{
+    admin::{is_duplicate_key, GetRoom},
+    room::{room_ranking, RankedMusic, RankingWindow},
+    settings::room_settings,
+    state::{ApiState, Presence},
+};
+
+/// Check if the user can access the room.
+///
+/// Admins can access every room, users only the room they joined,
+/// and event users every room of the event they joined until it expires.
+pub async fn has_room_access(
+    db: &DatabaseConnection,
+    user: &User,
+    room_id: RoomID,
+) -> Result<bool, DbErr> {
+    match user.role {
+        Role::Admin => Ok(true),
+        Role::User { room_id: user_room } => Ok(user_room == room_id),
+        Role::Event { event_id } => {
+            let count = room::Entity::find()
+                .filter(room::Column::PublicId.eq(room_id.value()))
+                .filter(room::Column::EventId.eq(event_id.value()))
+                .filter(room::Column::ExpirationDate.gt(Utc::now()))
+                .count(db)
+                .await?;
+            Ok(count > 0)
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GetEvent {
+    id: RoomID,
+    name: String,
+    creation: DateTime<Utc>,
+    expiration: DateTime<Utc>,
+    join_count: u32,
+    active_users: usize,
+    rooms: Vec<GetRoom>,
+}
+
+impl GetEvent {
+    fn new(model: event::Model, rooms: Vec<room::Model>, presence: &Presence) -> Self {
+        let rooms_id = rooms.iter().map(|room| RoomID::new(room.public_id));
+        Self {
+            id: RoomID::new(model.public_id),
+            name: model.name,
+            creation: model.creation_date,
+            expiration: model.expiration_date,
+            join_count: model.join_count,
+            active_users: presence.active_users_in(rooms_id),
+            rooms: rooms
+                .into_iter()
+                .map(|room| GetRoom::new(room, presence))
+                .collect(),
+        }
+    }
+}
+
+#[api_macro::error(internal_error, unauthorized)]
+pub enum GetEventError {
+    /// Event not found
+    #[status(StatusCode::NOT_FOUND)]
+    EventNotFound,
+}
+
+pub async fn get_events(
+    State(state): State<ApiState>,
+    user: User,
+) -> Result<Json<Vec<GetEvent>>, GetEventError> {
+    if user.role != Role::Admin {
+        return Err(GetEventError::Unauthorized);
+    }
+
+    let events = event::Entity::find()
+        .find_with_related(room::Entity)
+        .all(&state.db)
+        .await?;
+
+    let events = events
+        .into_iter()
+        .map(|(event, rooms)| GetEvent::new(event, rooms, &state.presence))
+        .collect();
+
+    Ok(Json(events))
+}
+
+pub async fn get_event(
+    State(state): State<ApiState>,
+    Path(event_id): Path<RoomID>,
+    user: User,
+) -> Result<Json<GetEvent>, GetEventError> {
+    if user.role != Role::Admin {
+        return Err(GetEventError::Unauthorized);
+    }
+
+    let event = event::Entity::find()
+        .filter(event::Column::PublicId.eq(event_id.value()))
+        .one(&state.db)
+        .await?
+        .ok_or(GetEventError::EventNotFound)?;
+
+    let rooms = event.find_related(room::Entity).all(&state.db).await?;
+
+    Ok(Json(GetEvent::new(event, rooms, &state.presence)))
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateEvent {
+    id: RoomID,
+    name: String,
+    expiration: DateTime<Utc>,
+}
+
+impl CreateEvent {
+    fn to_active_model(&self) -> event::ActiveModel {
+        event::ActiveModel {
+            public_id: Set(self.id.value()),
+            name: Set(self.name.clone()),
+            expiration_date: Set(self.expiration),
+            ..Default::default()
+        }
+    }
+}
+
+#[api_macro::error(internal_error, unauthorized)]
+pub enum CreateEventError {
+    /// Event id already exists
+    #[status(StatusCode::CONFLICT)]
+    EventIdAlreadyExists,
+}
+
+pub async fn create_event(
+    State(state): State<ApiState>,
+    user: User,
+    Json(event): Json<CreateEvent>,
+) -> Result<Json<GetEvent>, CreateEventError> {
+    if user.role != Role::Admin {
+        return Err(CreateEventError::Unauthorized);
+    }
+
+    event
+        .to_active_model()
+        .save(&state.db)
+        .await
+        .and_then(event::ActiveModel::try_into_model)
+        .map(|event| GetEvent::new(event, Vec::new(), &state.presence))
+        .map(Json)
+        .map_err(|err| {
+            if is_duplicate_key(&err) {
+                return CreateEventError::EventIdAlreadyExists;
+            }
+            CreateEventError::InternalError(err)
+        })
+}
+
+#[api_macro::error(internal_error, unauthorized)]
+pub enum DeleteEventError {
+    /// Event id does not exist
+    #[status(StatusCode::NOT_FOUND)]
+    EventIdDoesNotExist,
+}
+
+pub async fn delete_event(
+    State(state): State<ApiState>,
+    user: User,
+    Path(event_id): Path<RoomID>,
+) -> Result<(), DeleteEventError> {
+    if user.role != Role::Admin {
+        return Err(DeleteEventError::Unauthorized);
+    }
+
+    let txn = state.db.begin().await?;
+
+    // The rooms are kept, they are only detached from the event
+    room::Entity::update_many()
+        .col_expr(room::Column::EventId, Expr::value(Option::<u32>::None))
+        .filter(room::Column::EventId.eq(event_id.value()))
+        .exec(&txn)
+        .await?;
+
+    let rows_affected = event::Entity::delete_many()
+        .filter(event::Column::PublicId.eq(event_id.value()))
+        .exec(&txn)
+        .await?
+        .rows_affected;
+
+    if rows_affected == 0 {
+        return Err(DeleteEventError::EventIdDoesNotExist);
+    }
+    txn.commit().await?;
+
+    Ok(())
+}
+
+#[api_macro::error(internal_error, unauthorized)]
+pub enum EventRoomError {
+    /// Event not found
+    #[status(StatusCode::NOT_FOUND)]
+    EventNotFound,
+    /// Room not found
+    #[status(StatusCode::NOT_FOUND)]
+    RoomNotFound,
+}
+
+pub async fn add_room(
+    State(state): State<ApiState>,
+    user: User,
+    Path((event_id, room_id)): Path<(RoomID, RoomID)>,
+) -> Result<(), EventRoomError> {
+    if user.role != Role::Admin {
+        return Err(EventRoomError::Unauthorized);
+    }
+
+    event::Entity::find()
+        .filter(event::Column::PublicId.eq(event_id.value()))
+        .one(&state.db)
+        .await?
+        .ok_or(EventRoomError::EventNotFound)?;
+
+    let rows_affected = room::Entity::update_many()
+        .col_expr(room::Column::EventId, Expr::value(event_id.value()))
+        .filter(room::Column::PublicId.eq(room_id.value()))
+        .exec(&state.db)
+        .await?
+        .rows_affected;
+
+    match rows_affected {
+        0 => Err(EventRoomError::RoomNotFound),
+        _ => Ok(()),
+    }
+}
+
+pub async fn remove_room(
+    State(state): State<ApiState>,
+    user: User,
+    Path((event_id, room_id)): Path<(RoomID, RoomID)>,
+) -> Result<(), EventRoomError> {
+    if user.role != Role::Admin {
+        return Err(EventRoomError::Unauthorized);
+    }
+
+    let rows_affected = room::Entity::update_many()
+        .col_expr(room::Column::EventId, Expr::value(Option::<u32>::None))
+        .filter(room::Column::PublicId.eq(room_id.value()))
+        .filter(room::Column::EventId.eq(event_id.value()))
+        .exec(&state.db)
+        .await?
+        .rows_affected;
+
+    match rows_affected {
+        0 => Err(EventRoomError::RoomNotFound),
+        _ => Ok(()),
+    }
+}
+
+/// The maximum number of active users in all the rooms of an event.
+const MAX_EVENT_USERS: usize = 10000;
+
+#[api_macro::error(internal_error)]
+#[default_status(StatusCode::UNAUTHORIZED)]
+pub enum JoinEventError {
+    /// The event does not exist
+    #[status(StatusCode::NOT_FOUND)]
+    EventNotFound,
+    /// The event is full
+    EventFull,
+    /// The event is closed
+    EventExpired,
+}
+
+pub async fn join(
+    State(state): State<ApiState>,
+    Path(event_id): Path<RoomID>,
+) -> Result<Json<UserToken>, JoinEventError> {
+    // Same as the room join, slow down the brut force
+    sleep(Duration::from_secs(1)).await;
+
+    let event = event::Entity::find()
+        .filter(event::Column::PublicId.eq(event_id.value()))
+        .one(&state.db)
+        .await?;
+
+    let Some(event) = event else {
+        return Err(JoinEventError::EventNotFound);
+    };
+
+    if event.expiration_date < Utc::now() {
+        return Err(JoinEventError::EventExpired);
+    }
+
+    let rooms = event.find_related(room::Entity).all(&state.db).await?;
+    let rooms_id = rooms.iter().map(|room| RoomID::new(room.public_id));
+    if state.presence.active_users_in(rooms_id) >= MAX_EVENT_USERS {
+        return Err(JoinEventError::EventFull);
+    }
+
+    let row_affected = event::Entity::update_many()
+        .col_expr(
+            event::Column::JoinCount,
+            Expr::add(Expr::col(event::Column::JoinCount), 1),
+        )
+        // The filtering assume that the public id is unique
+        .filter(event::Column::PublicId.eq(event_id.value()))
+        .exec(&state.db)
+        .await?
+        .rows_affected;
+
+    match row_affected {
+        0 => Err(JoinEventError::EventNotFound),
+        _ => Ok(Json(
+            User::new_event_user(event_id).into_token(event.expiration_date),
+        )),
+    }
+}
+
+/// The ranking of a room of an event.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct EventRanking {
+    room: RoomID,
+    musics: Vec<RankedMusic>,
+}
+
+/// Get the ranking of each room of the event, with the ranking rules of the room.
+///
+/// The users only see the rooms not expired yet.
+pub async fn get_musics(
+    State(state): State<ApiState>,
+    Path(event_id): Path<RoomID>,
+    user: User,
+) -> Result<Json<Vec<EventRanking>>, GetEventError> {
+    if (Role::Event { event_id }) != user.role && user.role != Role::Admin {
+        return Err(GetEventError::Unauthorized);
+    }
+
+    let event = event::Entity::find()
+        .filter(event::Column::PublicId.eq(event_id.value()))
+        .one(&state.db)
+        .await?
+        .ok_or(GetEventError::EventNotFound)?;
+
+    let is_admin = user.role == Role::Admin;
+    let mut rooms = event.find_related(room::Entity);
+    if !is_admin {
+        rooms = rooms.filter(room::Column::ExpirationDate.gt(Utc::now()));
+    }
+
+    let mut rankings = Vec::new();
+    for room in rooms.all(&state.db).await? {
+        let room_id = RoomID::new(room.public_id);
+        let settings = room_settings(&state.db, room_id).await?;
+        let window = RankingWindow::default();
+        let mut musics = room_ranking(&state.db, room_id, &settings, window, is_admin).await?;
+        if !is_admin {
+            musics.truncate(settings.ranking_size as usize);
+        }
+        rankings.push(EventRanking {
+            room: room_id,
+            musics,
+        });
+    }
+
+    Ok(Json(rankings))
+}
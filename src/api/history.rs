@@ -0,0 +1,161 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{Duration, Utc};
+use serde::Deserialize;
+
+use entity::{vote::VoteValue, *};
+use sea_orm::{prelude::*, sea_query::SimpleExpr, QueryOrder, QuerySelect};
+
+use crate::utils::{
+    history::{period_start, to_csv, vote_series},
+    jwt::{Role, User},
+    room_id::RoomID,
+};
+
+use super::{state::ApiState, MusicId};
+
+#[api_macro::error(internal_error, unauthorized)]
+pub enum HistoryError {
+    /// The buckets must last 1 to 1440 minutes, with at most 10000 buckets
+    #[status(StatusCode::BAD_REQUEST)]
+    InvalidOptions,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum HistoryFormat {
+    #[default]
+    Json,
+    Csv,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct HistoryOptions {
+    /// The length of each bucket in minutes
+    #[serde(default = "HistoryOptions::default_bucket")]
+    bucket: u32,
+    /// Only the last minutes, since the first vote if `None`
+    minutes: Option<u32>,
+    #[serde(default)]
+    format: HistoryFormat,
+}
+
+impl HistoryOptions {
+    const MAX_BUCKET: u32 = 24 * 60;
+    const MAX_BUCKETS: i64 = 10_000;
+
+    fn default_bucket() -> u32 {
+        1
+    }
+
+    fn is_valid(&self) -> bool {
+        (1..=Self::MAX_BUCKET).contains(&self.bucket)
+    }
+}
+
+/// Get the votes cast for the music in the room, per bucket of time.
+pub async fn get_music_history(
+    State(state): State<ApiState>,
+    Path((room_id, music_id)): Path<(RoomID, MusicId)>,
+    Query(options): Query<HistoryOptions>,
+    user: User,
+) -> Result<Response, HistoryError> {
+    let filter = vote::Column::RoomId
+        .eq(room_id.value())
+        .and(vote::Column::MusicId.eq(music_id));
+    vote_history(
+        &state,
+        user,
+        filter,
+        options,
+        &format!("{room_id}-{music_id}"),
+    )
+    .await
+}
+
+/// Get the votes cast in the room, per bucket of time.
+pub async fn get_room_history(
+    State(state): State<ApiState>,
+    Path(room_id): Path<RoomID>,
+    Query(options): Query<HistoryOptions>,
+    user: User,
+) -> Result<Response, HistoryError> {
+    let filter = vote::Column::RoomId.eq(room_id.value());
+    vote_history(&state, user, filter, options, &room_id.to_string()).await
+}
+
+async fn vote_history(
+    state: &ApiState,
+    user: User,
+    filter: SimpleExpr,
+    options: HistoryOptions,
+    name: &str,
+) -> Result<Response, HistoryError> {
+    if user.role != Role::Admin {
+        return Err(HistoryError::Unauthorized);
+    }
+    if !options.is_valid() {
+        return Err(HistoryError::InvalidOptions);
+    }
+
+    let now = Utc::now();
+    let bucket = Duration::minutes(options.bucket.into());
+    // The history starts at the beginning of a bucket, the one of the first vote when no length is given
+    let start = match options.minutes {
+        Some(minutes) => Some(period_start(
+            now - Duration::minutes(minutes.into()),
+            bucket,
+        )),
+        None => vote::Entity::find()
+            .select_only()
+            .column(vote::Column::VoteDate)
+            .filter(filter.clone())
+            .order_by_asc(vote::Column::VoteDate)
+            .into_tuple::<DateTimeUtc>()
+            .one(&state.db)
+            .await?
+            .map(|first_vote| period_start(first_vote, bucket)),
+    };
+    let points = match start {
+        Some(start) => {
+            // Checked before loading the votes
+            let buckets = (now - start).num_minutes() / bucket.num_minutes();
+            if buckets >= HistoryOptions::MAX_BUCKETS {
+                return Err(HistoryError::InvalidOptions);
+            }
+
+            let votes: Vec<(DateTimeUtc, VoteValue)> = vote::Entity::find()
+                .select_only()
+                .column(vote::Column::VoteDate)
+                .column(vote::Column::Value)
+                .filter(filter)
+                .filter(vote::Column::VoteDate.gte(start))
+                .order_by_asc(vote::Column::VoteDate)
+                .into_tuple()
+                .all(&state.db)
+                .await?;
+            vote_series(&votes, start, now, bucket)
+        }
+        None => Vec::new(),
+    };
+
+    let response = match options.format {
+        HistoryFormat::Json => Json(points).into_response(),
+        HistoryFormat::Csv => {
+            let disposition = format!("attachment; filename=\"votes-{name}.csv\"");
+            (
+                [
+                    (header::CONTENT_TYPE, "text/csv".to_string()),
+                    (header::CONTENT_DISPOSITION, disposition),
+                ],
+                to_csv(&points),
+            )
+                .into_response()
+        }
+    };
+    Ok(response)
+}
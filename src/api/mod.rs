@@ -1,21 +1,30 @@
-use std::time::Duration;
-
 use axum::{
-    error_handling::HandleErrorLayer,
     http::StatusCode,
-    routing::{delete, get, post},
+    routing::{delete, get, post, put},
     Router,
 };
-use tower::{limit::ConcurrencyLimitLayer, ServiceBuilder};
+use tower::limit::ConcurrencyLimitLayer;
 use tower_http::trace::TraceLayer;
 
 use self::state::ApiState;
 
 mod admin;
+mod blocklist;
+mod dedication;
+mod energy;
+mod event;
+mod history;
+mod nickname;
+mod played;
+mod poll;
+mod queue;
+mod requester;
 mod room;
 mod search;
+mod settings;
 mod websocket;
 
+pub mod share;
 pub mod state;
 
 pub type MusicId = i64;
@@ -25,17 +34,7 @@ pub fn router(state: ApiState) -> Router {
     // This is ok because for now we only have one admin
     let admin_login = post(admin::login).layer(ConcurrencyLimitLayer::new(1));
     let room_join = get(room::join).layer(ConcurrencyLimitLayer::new(10));
-
-    let handle_error = |err| async move {
-        log::error!("Unhandled error: {}", err);
-        (StatusCode::INTERNAL_SERVER_ERROR, "Unhandled error")
-    };
-
-    // Deezer API rate limit is 50 requests per 5 seconds
-    let rate_limit = ServiceBuilder::new()
-        .layer(HandleErrorLayer::new(handle_error))
-        .buffer(128)
-        .rate_limit(40, Duration::from_secs(5));
+    let event_join = get(event::join).layer(ConcurrencyLimitLayer::new(10));
 
     Router::<ApiState>::new()
         .layer(TraceLayer::new_for_http())
@@ -47,9 +46,93 @@ pub fn router(state: ApiState) -> Router {
         .route("/room/:room/music/all", get(room::get_musics))
         .route("/room/:room/music/voted", get(room::get_voted_musics))
         .route("/room/:room/music/:music", get(room::get_music_detail))
+        .route(
+            "/room/:room/music/:music/history",
+            get(history::get_music_history),
+        )
+        .route("/room/:room/vote/history", get(history::get_room_history))
+        .route(
+            "/room/:room/settings",
+            get(settings::get_settings).post(settings::update_settings),
+        )
         .route("/room/:room/vote", post(room::vote))
-        .route("/room/:room/search", get(search::search).layer(rate_limit))
+        .route("/room/:room/vote/batch", post(room::batch_vote))
+        .route("/room/:room/vote/budget", get(room::get_vote_budget))
+        .route("/room/:room/vote/:music", delete(room::retract_vote))
+        .route("/room/:room/heartbeat", post(room::heartbeat))
+        .route("/room/:room/signal", post(energy::send_signal))
+        .route(
+            "/room/:room/poll",
+            get(poll::get_poll).post(poll::create_poll),
+        )
+        .route("/room/:room/poll/:poll/vote", post(poll::vote_poll))
+        .route("/room/:room/poll/:poll/close", post(poll::close_poll))
+        .route("/room/:room/energy", get(energy::get_energy))
+        .route(
+            "/room/:room/energy/history",
+            get(energy::get_energy_history),
+        )
+        .route("/room/:room/alerts", get(room::get_abuse_alerts))
+        .route("/room/:room/played", get(played::get_played))
+        .route(
+            "/room/:room/played/:music",
+            post(played::mark_played).delete(played::unmark_played),
+        )
+        .route("/room/:room/requests", post(queue::update_requests))
+        .route("/room/:room/requesters", get(requester::get_requesters))
+        .route(
+            "/room/:room/requesters/me",
+            get(requester::get_played_requests),
+        )
+        .route("/room/:room/dedications", get(dedication::get_dedications))
+        .route(
+            "/room/:room/dedications/:dedication",
+            put(dedication::moderate_dedication),
+        )
+        .route("/room/:room/nickname", put(room::set_nickname))
+        .route("/room/:room/nicknames", get(nickname::get_nicknames))
+        .route(
+            "/room/:room/nicknames/:user",
+            delete(nickname::reset_nickname),
+        )
+        .route(
+            "/room/:room/queue",
+            get(queue::get_queue).put(queue::reorder_queue),
+        )
+        .route(
+            "/room/:room/now-playing",
+            get(played::get_now_playing).put(played::set_now_playing),
+        )
+        .route("/room/:room/search", get(search::search))
         .route("/room/:room/ws", get(websocket::handle_request))
+        .route(
+            "/room/:room/audience/ws",
+            get(websocket::handle_audience_request),
+        )
+        .route("/room/:room/qr.svg", get(share::qr_svg))
+        .route("/room/:room/qr.png", get(share::qr_png))
+        .route("/room/:room/flyer.pdf", get(share::flyer))
+        .route(
+            "/room/:room/blocklist",
+            get(blocklist::get_room_rules).post(blocklist::create_room_rule),
+        )
+        .route(
+            "/blocklist",
+            get(blocklist::get_global_rules).post(blocklist::create_global_rule),
+        )
+        .route("/blocklist/:rule", delete(blocklist::delete_rule))
+        .route("/event/all", get(event::get_events))
+        .route("/event", post(event::create_event))
+        .route(
+            "/event/:event",
+            get(event::get_event).delete(event::delete_event),
+        )
+        .route("/event/:event/join", event_join)
+        .route("/event/:event/music/all", get(event::get_musics))
+        .route(
+            "/event/:event/room/:room",
+            post(event::add_room).delete(event::remove_room),
+        )
         .with_state(state)
         .fallback(api_fallback)
 }
@@ -0,0 +1,147 @@
+use std::collections::HashMap;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use entity::*;
+use sea_orm::{prelude::*, ConnectionTrait, QueryOrder, Set};
+
+use crate::utils::{
+    jwt::{Role, User},
+    nickname::nickname_key,
+    room_id::RoomID,
+};
+
+use super::state::ApiState;
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Nickname {
+    user: Uuid,
+    nickname: String,
+    creation: DateTime<Utc>,
+}
+
+impl From<nickname::Model> for Nickname {
+    fn from(model: nickname::Model) -> Self {
+        Self {
+            user: model.user_token,
+            nickname: model.nickname,
+            creation: model.creation_date,
+        }
+    }
+}
+
+/// Get the nickname of the user in the room, if they chose one.
+pub(super) async fn user_nickname(
+    db: &impl ConnectionTrait,
+    room_id: RoomID,
+    user_token: Uuid,
+) -> Result<Option<String>, DbErr> {
+    let nickname = nickname::Entity::find_by_id((room_id.value(), user_token))
+        .one(db)
+        .await?;
+    Ok(nickname.map(|nickname| nickname.nickname))
+}
+
+/// Get the nicknames of the users of the room.
+pub(super) async fn room_nicknames(
+    db: &DatabaseConnection,
+    room_id: RoomID,
+) -> Result<HashMap<Uuid, String>, DbErr> {
+    let nicknames = nickname::Entity::find()
+        .filter(nickname::Column::RoomId.eq(room_id.value()))
+        .all(db)
+        .await?;
+    Ok(nicknames
+        .into_iter()
+        .map(|nickname| (nickname.user_token, nickname.nickname))
+        .collect())
+}
+
+/// Check if another user of the room already has the nickname, ignoring the case.
+pub(super) async fn is_nickname_taken(
+    db: &DatabaseConnection,
+    room_id: RoomID,
+    user_token: Uuid,
+    nickname: &str,
+) -> Result<bool, DbErr> {
+    let taken = nickname::Entity::find()
+        .filter(nickname::Column::RoomId.eq(room_id.value()))
+        .filter(nickname::Column::NameKey.eq(nickname_key(nickname)))
+        .filter(nickname::Column::UserToken.ne(user_token))
+        .one(db)
+        .await?;
+    Ok(taken.is_some())
+}
+
+/// Save the nickname of a user, failing with a unique constraint violation if it is taken.
+pub(super) async fn save_nickname(
+    db: &impl ConnectionTrait,
+    room_id: RoomID,
+    user_token: Uuid,
+    nickname: String,
+) -> Result<(), DbErr> {
+    nickname::ActiveModel {
+        room_id: Set(room_id.value()),
+        user_token: Set(user_token),
+        name_key: Set(nickname_key(&nickname)),
+        nickname: Set(nickname),
+        creation_date: Set(Utc::now()),
+    }
+    .insert(db)
+    .await?;
+    Ok(())
+}
+
+#[api_macro::error(internal_error, unauthorized)]
+pub enum NicknameError {
+    /// Nickname not found
+    #[status(StatusCode::NOT_FOUND)]
+    NicknameNotFound,
+}
+
+/// Get the nicknames of the users of the room, in alphabetical order.
+pub async fn get_nicknames(
+    State(state): State<ApiState>,
+    Path(room_id): Path<RoomID>,
+    user: User,
+) -> Result<Json<Vec<Nickname>>, NicknameError> {
+    if user.role != Role::Admin {
+        return Err(NicknameError::Unauthorized);
+    }
+
+    let nicknames = nickname::Entity::find()
+        .filter(nickname::Column::RoomId.eq(room_id.value()))
+        .order_by_asc(nickname::Column::NameKey)
+        .all(&state.db)
+        .await?;
+
+    Ok(Json(nicknames.into_iter().map(Nickname::from).collect()))
+}
+
+/// Remove an offensive nickname, the user staying in the room anonymously.
+pub async fn reset_nickname(
+    State(state): State<ApiState>,
+    Path((room_id, user_token)): Path<(RoomID, Uuid)>,
+    user: User,
+) -> Result<(), NicknameError> {
+    if user.role != Role::Admin {
+        return Err(NicknameError::Unauthorized);
+    }
+
+    let deleted = nickname::Entity::delete_by_id((room_id.value(), user_token))
+        .exec(&state.db)
+        .await?
+        .rows_affected;
+
+    match deleted {
+        0 => Err(NicknameError::NicknameNotFound),
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,342 @@
+use std::collections::HashSet;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use entity::{vote::VoteValue, *};
+use sea_orm::{
+    prelude::*,
+    sea_query::{Expr, OnConflict, SelectStatement},
+    FromQueryResult, QueryOrder, QuerySelect, QueryTrait, Set, TransactionTrait,
+};
+
+use crate::utils::{
+    jwt::{Role, User},
+    room_id::RoomID,
+};
+
+use super::{
+    event::has_room_access,
+    requester::notify_requesters,
+    search::{get_music_or_store_music, MusicError},
+    state::ApiState,
+    websocket::{AudienceEvent, NowPlayingEvent, RoomEvent},
+    MusicId,
+};
+
+#[derive(Serialize, Deserialize, FromQueryResult, Debug)]
+pub struct PlayedMusic {
+    music_id: MusicId,
+    title: String,
+    artist: String,
+    preview_url: Option<String>,
+    image_hash: Option<String>,
+    played_at: DateTimeUtc,
+}
+
+#[api_macro::error(internal_error, unauthorized)]
+pub enum PlayedError {
+    /// Room not found
+    #[status(StatusCode::NOT_FOUND)]
+    RoomNotFound,
+    /// Deezer is unavailable, try again later
+    #[status(StatusCode::BAD_GATEWAY)]
+    DeezerUnavailable,
+    /// The music was not played in the room
+    #[status(StatusCode::BAD_REQUEST)]
+    NotPlayed,
+}
+
+/// Select the ids of the musics already played in the room.
+pub(super) fn played_ids(room_id: RoomID) -> SelectStatement {
+    played::Entity::find()
+        .select_only()
+        .column(played::Column::MusicId)
+        .filter(played::Column::RoomId.eq(room_id.value()))
+        .into_query()
+}
+
+/// Get the musics already played in the room.
+pub(super) async fn played_musics(
+    db: &impl ConnectionTrait,
+    room_id: RoomID,
+) -> Result<HashSet<MusicId>, DbErr> {
+    let played = played::Entity::find()
+        .select_only()
+        .column(played::Column::MusicId)
+        .filter(played::Column::RoomId.eq(room_id.value()))
+        .into_tuple::<MusicId>()
+        .all(db)
+        .await?;
+    Ok(played.into_iter().collect())
+}
+
+/// Get the played musics of the room, the last played first.
+pub async fn get_played(
+    State(state): State<ApiState>,
+    Path(room_id): Path<RoomID>,
+    user: User,
+) -> Result<Json<Vec<PlayedMusic>>, PlayedError> {
+    if !has_room_access(&state.db, &user, room_id).await? {
+        return Err(PlayedError::Unauthorized);
+    }
+    state.record_activity(room_id, &user);
+
+    played_query(room_id)
+        .order_by_desc(played::Column::PlayedAt)
+        .into_model()
+        .all(&state.db)
+        .await
+        .map(Json)
+        .map_err(From::from)
+}
+
+pub async fn mark_played(
+    State(state): State<ApiState>,
+    Path((room_id, music_id)): Path<(RoomID, MusicId)>,
+    user: User,
+) -> Result<(), PlayedError> {
+    if user.role != Role::Admin {
+        return Err(PlayedError::Unauthorized);
+    }
+
+    find_room(&state.db, room_id).await?;
+    mark_musics_played(&state, room_id, &[music_id]).await
+}
+
+pub async fn unmark_played(
+    State(state): State<ApiState>,
+    Path((room_id, music_id)): Path<(RoomID, MusicId)>,
+    user: User,
+) -> Result<(), PlayedError> {
+    if user.role != Role::Admin {
+        return Err(PlayedError::Unauthorized);
+    }
+
+    let room = find_room(&state.db, room_id).await?;
+
+    // The likers are saved again if the music is played again
+    let txn = state.db.begin().await?;
+    let deleted = played::Entity::delete_many()
+        .filter(played::Column::RoomId.eq(room_id.value()))
+        .filter(played::Column::MusicId.eq(music_id))
+        .exec(&txn)
+        .await?
+        .rows_affected;
+
+    if deleted == 0 {
+        return Err(PlayedError::NotPlayed);
+    }
+
+    played_request::Entity::delete_many()
+        .filter(played_request::Column::RoomId.eq(room_id.value()))
+        .filter(played_request::Column::MusicId.eq(music_id))
+        .exec(&txn)
+        .await?;
+    txn.commit().await?;
+
+    // A music not played can't be playing
+    if room.now_playing == Some(music_id) {
+        update_now_playing(&state, room_id, None).await?;
+    }
+
+    Ok(())
+}
+
+/// Get the music playing in the room, if any.
+pub async fn get_now_playing(
+    State(state): State<ApiState>,
+    Path(room_id): Path<RoomID>,
+    user: User,
+) -> Result<Json<Option<PlayedMusic>>, PlayedError> {
+    if !has_room_access(&state.db, &user, room_id).await? {
+        return Err(PlayedError::Unauthorized);
+    }
+    state.record_activity(room_id, &user);
+
+    let room = find_room(&state.db, room_id).await?;
+    let Some(music_id) = room.now_playing else {
+        return Ok(Json(None));
+    };
+
+    played_query(room_id)
+        .filter(played::Column::MusicId.eq(music_id))
+        .into_model()
+        .one(&state.db)
+        .await
+        .map(Json)
+        .map_err(From::from)
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct NowPlayingBody {
+    /// The music playing, or `None` when nothing is playing
+    music_id: Option<MusicId>,
+}
+
+/// Set the music playing in the room, marking it as played.
+pub async fn set_now_playing(
+    State(state): State<ApiState>,
+    Path(room_id): Path<RoomID>,
+    user: User,
+    Json(body): Json<NowPlayingBody>,
+) -> Result<(), PlayedError> {
+    if user.role != Role::Admin {
+        return Err(PlayedError::Unauthorized);
+    }
+
+    find_room(&state.db, room_id).await?;
+
+    if let Some(music_id) = body.music_id {
+        mark_musics_played(&state, room_id, &[music_id]).await?;
+    }
+
+    update_now_playing(&state, room_id, body.music_id).await?;
+
+    Ok(())
+}
+
+async fn find_room(db: &DatabaseConnection, room_id: RoomID) -> Result<room::Model, PlayedError> {
+    room::Entity::find()
+        .filter(room::Column::PublicId.eq(room_id.value()))
+        .one(db)
+        .await?
+        .ok_or(PlayedError::RoomNotFound)
+}
+
+/// Select the played musics of the room with their details.
+fn played_query(room_id: RoomID) -> Select<played::Entity> {
+    played::Entity::find()
+        .select_only()
+        .column(played::Column::MusicId)
+        .column(music::Column::Title)
+        .column(music::Column::Artist)
+        .column(music::Column::PreviewUrl)
+        .column(music::Column::ImageHash)
+        .column(played::Column::PlayedAt)
+        .inner_join(music::Entity)
+        .filter(played::Column::RoomId.eq(room_id.value()))
+}
+
+/// Mark the musics as played now, storing them first if needed.
+///
+/// The musics leave the queue, their request keeping the decision of the DJ in case they are
+/// unmarked, and the users who liked them are notified.
+/// Nothing changes for the musics already played.
+pub(super) async fn mark_musics_played(
+    state: &ApiState,
+    room_id: RoomID,
+    music_ids: &[MusicId],
+) -> Result<(), PlayedError> {
+    // The musics may be fetched from Deezer, before the transaction
+    let mut musics = Vec::with_capacity(music_ids.len());
+    for &music_id in music_ids {
+        let music = get_music_or_store_music(state, music_id)
+            .await
+            .map_err(|e| match e {
+                MusicError::Database(e) => PlayedError::InternalError(e),
+                MusicError::Deezer(_) => PlayedError::DeezerUnavailable,
+            })?;
+        musics.push(music);
+    }
+
+    let txn = state.db.begin().await?;
+
+    let now = Utc::now();
+    let mut newly_played = Vec::new();
+    for music in musics {
+        let played = played::ActiveModel {
+            room_id: Set(room_id.value()),
+            music_id: Set(music.id),
+            played_at: Set(now),
+            ..Default::default()
+        };
+
+        let inserted = played::Entity::insert(played)
+            .on_conflict(
+                OnConflict::columns([played::Column::RoomId, played::Column::MusicId])
+                    .do_nothing()
+                    .to_owned(),
+            )
+            .exec_without_returning(&txn)
+            .await?;
+        if inserted > 0 {
+            save_requesters(&txn, room_id, music.id).await?;
+            newly_played.push(music);
+        }
+    }
+
+    txn.commit().await?;
+
+    // The musics are played even if the users can't be notified
+    for music in newly_played {
+        if let Err(e) = notify_requesters(state, room_id, &music).await {
+            log::error!(
+                "Failed to notify the requesters of music {}: {}",
+                music.id,
+                e
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Keep the users liking the music when it is played, their vote being free to change afterwards.
+async fn save_requesters(
+    db: &impl ConnectionTrait,
+    room_id: RoomID,
+    music_id: MusicId,
+) -> Result<(), DbErr> {
+    let likers: Vec<Uuid> = current_vote::Entity::find()
+        .select_only()
+        .column(current_vote::Column::UserToken)
+        .filter(current_vote::Column::RoomId.eq(room_id.value()))
+        .filter(current_vote::Column::MusicId.eq(music_id))
+        .filter(current_vote::Column::Value.eq(VoteValue::Like))
+        .into_tuple()
+        .all(db)
+        .await?;
+    if likers.is_empty() {
+        return Ok(());
+    }
+
+    let requests = likers
+        .into_iter()
+        .map(|user_token| played_request::ActiveModel {
+            room_id: Set(room_id.value()),
+            music_id: Set(music_id),
+            user_token: Set(user_token),
+        });
+    played_request::Entity::insert_many(requests)
+        .exec(db)
+        .await?;
+    Ok(())
+}
+
+async fn update_now_playing(
+    state: &ApiState,
+    room_id: RoomID,
+    music_id: Option<MusicId>,
+) -> Result<(), DbErr> {
+    room::Entity::update_many()
+        .col_expr(room::Column::NowPlaying, Expr::value(music_id))
+        .filter(room::Column::PublicId.eq(room_id.value()))
+        .exec(&state.db)
+        .await?;
+
+    let event = NowPlayingEvent { music_id };
+    state
+        .rooms_channels
+        .send(room_id, RoomEvent::NowPlaying(event));
+    state
+        .audience_channels
+        .send(room_id, AudienceEvent::NowPlaying(event));
+
+    Ok(())
+}
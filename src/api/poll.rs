@@ -0,0 +1,461 @@
+use std::collections::HashSet;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+use entity::*;
+use sea_orm::{
+    prelude::*,
+    sea_query::{Alias, Expr, OnConflict, Order, Query},
+    FromQueryResult, JoinType, PaginatorTrait, QueryOrder, Set,
+};
+
+use crate::utils::{
+    jwt::{Role, User},
+    room_id::RoomID,
+};
+
+use super::{
+    event::has_room_access,
+    room::begin_write,
+    state::ApiState,
+    websocket::{AudienceEvent, RoomEvent},
+    MusicId,
+};
+
+/// The shortest poll, in seconds.
+const MIN_POLL_DURATION: u32 = 10;
+/// The longest poll, in seconds.
+const MAX_POLL_DURATION: u32 = 60 * 60;
+/// The delays before trying again to close a poll at its deadline, in seconds.
+const CLOSE_RETRY_DELAYS: [u64; 4] = [1, 5, 30, 120];
+
+/// A music of a poll with its votes.
+#[derive(Serialize, Deserialize, FromQueryResult, Debug, Clone)]
+pub struct PollChoice {
+    music_id: MusicId,
+    title: String,
+    artist: String,
+    preview_url: Option<String>,
+    image_hash: Option<String>,
+    votes: u32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PollResults {
+    id: u32,
+    closes_at: DateTime<Utc>,
+    closed: bool,
+    /// The most voted music once closed, `None` without votes
+    winner: Option<MusicId>,
+    /// The musics in the order given by the DJ
+    choices: Vec<PollChoice>,
+}
+
+#[api_macro::error(internal_error, unauthorized)]
+pub enum PollError {
+    /// Room not found
+    #[status(StatusCode::NOT_FOUND)]
+    RoomNotFound,
+    /// Poll not found
+    #[status(StatusCode::NOT_FOUND)]
+    PollNotFound,
+    /// A poll needs 2 to 4 different musics and must last 10 seconds to an hour
+    #[status(StatusCode::BAD_REQUEST)]
+    InvalidPoll,
+    /// The musics of a poll must have been voted
+    #[status(StatusCode::BAD_REQUEST)]
+    MusicNotFound,
+    /// A poll is already open in this room
+    #[status(StatusCode::CONFLICT)]
+    PollAlreadyOpen,
+    /// The poll is closed
+    #[status(StatusCode::BAD_REQUEST)]
+    PollClosed,
+    /// The music is not in the poll
+    #[status(StatusCode::BAD_REQUEST)]
+    MusicNotInPoll,
+    /// Already voted in this poll
+    #[status(StatusCode::BAD_REQUEST)]
+    AlreadyVoted,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CreatePoll {
+    music_ids: Vec<MusicId>,
+    /// The duration of the poll in seconds
+    duration: u32,
+}
+
+impl CreatePoll {
+    fn is_valid(&self) -> bool {
+        let distinct: HashSet<MusicId> = self.music_ids.iter().copied().collect();
+        (2..=4).contains(&self.music_ids.len())
+            && distinct.len() == self.music_ids.len()
+            && (MIN_POLL_DURATION..=MAX_POLL_DURATION).contains(&self.duration)
+    }
+}
+
+/// Ask the audience to choose between a few musics until the deadline.
+pub async fn create_poll(
+    State(state): State<ApiState>,
+    Path(room_id): Path<RoomID>,
+    user: User,
+    Json(create): Json<CreatePoll>,
+) -> Result<Json<PollResults>, PollError> {
+    if user.role != Role::Admin {
+        return Err(PollError::Unauthorized);
+    }
+
+    if !create.is_valid() {
+        return Err(PollError::InvalidPoll);
+    }
+
+    room::Entity::find()
+        .filter(room::Column::PublicId.eq(room_id.value()))
+        .one(&state.db)
+        .await?
+        .ok_or(PollError::RoomNotFound)?;
+
+    let stored = music::Entity::find()
+        .filter(music::Column::Id.is_in(create.music_ids.clone()))
+        .count(&state.db)
+        .await?;
+    if stored != create.music_ids.len() as u64 {
+        return Err(PollError::MusicNotFound);
+    }
+
+    let txn = begin_write(&state.db).await?;
+
+    // A poll past its deadline is closed, even if closing it failed
+    let now = Utc::now();
+    let open = poll::Entity::find()
+        .filter(poll::Column::RoomId.eq(room_id.value()))
+        .filter(poll::Column::Closed.eq(false))
+        .filter(poll::Column::ClosesAt.gt(now))
+        .count(&txn)
+        .await?;
+    if open > 0 {
+        return Err(PollError::PollAlreadyOpen);
+    }
+
+    let poll = poll::ActiveModel {
+        room_id: Set(room_id.value()),
+        creation_date: Set(now),
+        closes_at: Set(now + Duration::seconds(create.duration.into())),
+        closed: Set(false),
+        winner: Set(None),
+        ..Default::default()
+    }
+    .insert(&txn)
+    .await?;
+
+    let options = create
+        .music_ids
+        .iter()
+        .enumerate()
+        .map(|(position, &music_id)| poll_option::ActiveModel {
+            poll_id: Set(poll.id),
+            music_id: Set(music_id),
+            position: Set(position as u32),
+        });
+    poll_option::Entity::insert_many(options).exec(&txn).await?;
+
+    txn.commit().await?;
+
+    tokio::spawn(close_poll_at(
+        state.clone(),
+        room_id,
+        poll.id,
+        poll.closes_at,
+    ));
+
+    let results = poll_results(&state.db, &poll).await?;
+    send_poll(&state, room_id, &results);
+
+    Ok(Json(results))
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CurrentPoll {
+    #[serde(flatten)]
+    results: PollResults,
+    /// The music chosen by the user, if they voted
+    voted: Option<MusicId>,
+}
+
+/// Get the last poll of the room, open or closed.
+pub async fn get_poll(
+    State(state): State<ApiState>,
+    Path(room_id): Path<RoomID>,
+    user: User,
+) -> Result<Json<Option<CurrentPoll>>, PollError> {
+    if !has_room_access(&state.db, &user, room_id).await? {
+        return Err(PollError::Unauthorized);
+    }
+    state.record_activity(room_id, &user);
+
+    let poll = poll::Entity::find()
+        .filter(poll::Column::RoomId.eq(room_id.value()))
+        .order_by_desc(poll::Column::Id)
+        .one(&state.db)
+        .await?;
+    let Some(poll) = poll else {
+        return Ok(Json(None));
+    };
+
+    let voted = poll_vote::Entity::find_by_id((poll.id, user.uid))
+        .one(&state.db)
+        .await?
+        .map(|vote| vote.music_id);
+    let results = poll_results(&state.db, &poll).await?;
+
+    Ok(Json(Some(CurrentPoll { results, voted })))
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PollVoteBody {
+    music_id: MusicId,
+}
+
+pub async fn vote_poll(
+    State(state): State<ApiState>,
+    Path((room_id, poll_id)): Path<(RoomID, u32)>,
+    user: User,
+    Json(body): Json<PollVoteBody>,
+) -> Result<Json<PollResults>, PollError> {
+    if !has_room_access(&state.db, &user, room_id).await? {
+        return Err(PollError::Unauthorized);
+    }
+    state.record_activity(room_id, &user);
+
+    // Closing the poll takes the lock too, so no vote is left out of the winner
+    let txn = begin_write(&state.db).await?;
+    let poll = find_poll(&txn, room_id, poll_id).await?;
+    if poll.closed || poll.closes_at <= Utc::now() {
+        return Err(PollError::PollClosed);
+    }
+
+    poll_option::Entity::find_by_id((poll.id, body.music_id))
+        .one(&txn)
+        .await?
+        .ok_or(PollError::MusicNotInPoll)?;
+
+    let vote = poll_vote::ActiveModel {
+        poll_id: Set(poll.id),
+        user_token: Set(user.uid),
+        music_id: Set(body.music_id),
+        vote_date: Set(Utc::now()),
+    };
+    let inserted = poll_vote::Entity::insert(vote)
+        .on_conflict(
+            OnConflict::columns([poll_vote::Column::PollId, poll_vote::Column::UserToken])
+                .do_nothing()
+                .to_owned(),
+        )
+        .exec_without_returning(&txn)
+        .await?;
+    if inserted == 0 {
+        return Err(PollError::AlreadyVoted);
+    }
+    let results = poll_results(&txn, &poll).await?;
+    txn.commit().await?;
+
+    send_poll(&state, room_id, &results);
+
+    Ok(Json(results))
+}
+
+/// Close the poll before its deadline.
+pub async fn close_poll(
+    State(state): State<ApiState>,
+    Path((room_id, poll_id)): Path<(RoomID, u32)>,
+    user: User,
+) -> Result<Json<PollResults>, PollError> {
+    if user.role != Role::Admin {
+        return Err(PollError::Unauthorized);
+    }
+
+    let poll = find_poll(&state.db, room_id, poll_id).await?;
+    if poll.closed {
+        return Err(PollError::PollClosed);
+    }
+
+    finish_poll(&state, room_id, poll.id)
+        .await?
+        .ok_or(PollError::PollClosed)
+        .map(Json)
+}
+
+/// Close the open polls at their deadline, the ones already past being closed now.
+pub(super) async fn schedule_open_polls(state: &ApiState) -> Result<(), DbErr> {
+    let polls = poll::Entity::find()
+        .filter(poll::Column::Closed.eq(false))
+        .all(&state.db)
+        .await?;
+
+    for poll in polls {
+        let room_id = RoomID::new(poll.room_id);
+        tokio::spawn(close_poll_at(
+            state.clone(),
+            room_id,
+            poll.id,
+            poll.closes_at,
+        ));
+    }
+
+    Ok(())
+}
+
+async fn close_poll_at(state: ApiState, room_id: RoomID, poll_id: u32, closes_at: DateTime<Utc>) {
+    let remaining = (closes_at - Utc::now()).to_std().unwrap_or_default();
+    tokio::time::sleep(remaining).await;
+
+    let mut retry_delays = CLOSE_RETRY_DELAYS.iter();
+    loop {
+        let e = match finish_poll(&state, room_id, poll_id).await {
+            Ok(_) => return,
+            Err(e) => e,
+        };
+        let Some(&delay) = retry_delays.next() else {
+            log::error!(
+                "Failed to close poll {} of room {}: {}",
+                poll_id,
+                room_id,
+                e
+            );
+            return;
+        };
+        log::warn!(
+            "Failed to close poll {} of room {}, trying again in {}s: {}",
+            poll_id,
+            room_id,
+            delay,
+            e
+        );
+        tokio::time::sleep(std::time::Duration::from_secs(delay)).await;
+    }
+}
+
+/// Close the poll and store its winner, the first music given by the DJ winning the ties.
+///
+/// Returns `None` if the poll was already closed or deleted.
+async fn finish_poll(
+    state: &ApiState,
+    room_id: RoomID,
+    poll_id: u32,
+) -> Result<Option<PollResults>, DbErr> {
+    // The lock keeps the votes from changing between the count and the closing
+    let txn = begin_write(&state.db).await?;
+    let poll = poll::Entity::find_by_id(poll_id)
+        .filter(poll::Column::RoomId.eq(room_id.value()))
+        .filter(poll::Column::Closed.eq(false))
+        .one(&txn)
+        .await?;
+    let Some(poll) = poll else {
+        return Ok(None);
+    };
+
+    let mut results = poll_results(&txn, &poll).await?;
+    let winner = results
+        .choices
+        .iter()
+        .filter(|choice| choice.votes > 0)
+        .fold(None::<&PollChoice>, |best, choice| match best {
+            Some(best) if best.votes >= choice.votes => Some(best),
+            _ => Some(choice),
+        })
+        .map(|choice| choice.music_id);
+
+    poll::Entity::update_many()
+        .col_expr(poll::Column::Closed, Expr::value(true))
+        .col_expr(poll::Column::Winner, Expr::value(winner))
+        .filter(poll::Column::Id.eq(poll.id))
+        .exec(&txn)
+        .await?;
+    txn.commit().await?;
+
+    results.closed = true;
+    results.winner = winner;
+    send_poll(state, room_id, &results);
+
+    Ok(Some(results))
+}
+
+/// Send the poll to the DJ and to the users.
+fn send_poll(state: &ApiState, room_id: RoomID, results: &PollResults) {
+    state
+        .rooms_channels
+        .send(room_id, RoomEvent::Poll(results.clone()));
+    state
+        .audience_channels
+        .send(room_id, AudienceEvent::Poll(results.clone()));
+}
+
+async fn find_poll(
+    db: &impl ConnectionTrait,
+    room_id: RoomID,
+    poll_id: u32,
+) -> Result<poll::Model, PollError> {
+    poll::Entity::find_by_id(poll_id)
+        .filter(poll::Column::RoomId.eq(room_id.value()))
+        .one(db)
+        .await?
+        .ok_or(PollError::PollNotFound)
+}
+
+async fn poll_results(db: &impl ConnectionTrait, poll: &poll::Model) -> Result<PollResults, DbErr> {
+    let statement = Query::select()
+        .column((poll_option::Entity, poll_option::Column::MusicId))
+        .columns([
+            music::Column::Title,
+            music::Column::Artist,
+            music::Column::PreviewUrl,
+            music::Column::ImageHash,
+        ])
+        .expr_as(
+            Expr::col((poll_vote::Entity, poll_vote::Column::UserToken)).count(),
+            Alias::new("votes"),
+        )
+        .from(poll_option::Entity)
+        .join(
+            JoinType::InnerJoin,
+            music::Entity,
+            Expr::col((poll_option::Entity, poll_option::Column::MusicId))
+                .equals((music::Entity, music::Column::Id)),
+        )
+        .join(
+            JoinType::LeftJoin,
+            poll_vote::Entity,
+            Expr::col((poll_vote::Entity, poll_vote::Column::PollId))
+                .equals((poll_option::Entity, poll_option::Column::PollId))
+                .and(
+                    Expr::col((poll_vote::Entity, poll_vote::Column::MusicId))
+                        .equals((poll_option::Entity, poll_option::Column::MusicId)),
+                ),
+        )
+        .and_where(Expr::col((poll_option::Entity, poll_option::Column::PollId)).eq(poll.id))
+        .group_by_col((poll_option::Entity, poll_option::Column::MusicId))
+        .order_by(
+            (poll_option::Entity, poll_option::Column::Position),
+            Order::Asc,
+        )
+        .take();
+
+    let choices = PollChoice::find_by_statement(db.get_database_backend().build(&statement))
+        .all(db)
+        .await?;
+
+    Ok(PollResults {
+        id: poll.id,
+        closes_at: poll.closes_at,
+        closed: poll.closed,
+        winner: poll.winner,
+        choices,
+    })
+}
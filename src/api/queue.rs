@@ -0,0 +1,377 @@
+use std::collections::{HashMap, HashSet};
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use entity::{request::Decision, *};
+use sea_orm::{
+    prelude::*,
+    sea_query::{Expr, OnConflict, SimpleExpr},
+    FromQueryResult, PaginatorTrait, QueryOrder, QuerySelect, Set, TransactionTrait,
+};
+
+use crate::utils::{
+    jwt::{Role, User},
+    room_id::RoomID,
+};
+
+use super::{
+    event::has_room_access,
+    played::{mark_musics_played, played_ids, played_musics, PlayedError},
+    state::ApiState,
+    MusicId,
+};
+
+/// The status of a requested music in a room.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum RequestStatus {
+    /// The DJ has not decided yet
+    #[default]
+    Pending,
+    /// The music is in the queue
+    Accepted,
+    Rejected,
+    Played,
+}
+
+impl From<Decision> for RequestStatus {
+    fn from(decision: Decision) -> Self {
+        match decision {
+            Decision::Accepted => RequestStatus::Accepted,
+            Decision::Rejected => RequestStatus::Rejected,
+        }
+    }
+}
+
+/// The status of a music with the reason of the DJ.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Request {
+    pub status: RequestStatus,
+    /// Why the music was rejected, if the DJ gave a reason
+    pub reason: Option<String>,
+}
+
+/// Get the status of the requested musics of the room, the musics not listed are pending.
+pub(super) async fn room_requests(
+    db: &DatabaseConnection,
+    room_id: RoomID,
+) -> Result<HashMap<MusicId, Request>, DbErr> {
+    let mut requests: HashMap<MusicId, Request> = request::Entity::find()
+        .filter(request::Column::RoomId.eq(room_id.value()))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|model| {
+            let request = Request {
+                status: model.decision.into(),
+                reason: model.reason,
+            };
+            (model.music_id, request)
+        })
+        .collect();
+
+    // A played music is played whatever the decision of the DJ
+    for music_id in played_musics(db, room_id).await? {
+        requests.insert(
+            music_id,
+            Request {
+                status: RequestStatus::Played,
+                reason: None,
+            },
+        );
+    }
+
+    Ok(requests)
+}
+
+/// Get the status of a music in the room, pending if it was not requested.
+pub(super) async fn music_request(
+    db: &DatabaseConnection,
+    room_id: RoomID,
+    music_id: MusicId,
+) -> Result<Request, DbErr> {
+    let played = played::Entity::find()
+        .filter(played::Column::RoomId.eq(room_id.value()))
+        .filter(played::Column::MusicId.eq(music_id))
+        .count(db)
+        .await?;
+    if played > 0 {
+        return Ok(Request {
+            status: RequestStatus::Played,
+            reason: None,
+        });
+    }
+
+    let request = request::Entity::find()
+        .filter(request::Column::RoomId.eq(room_id.value()))
+        .filter(request::Column::MusicId.eq(music_id))
+        .one(db)
+        .await?
+        .map(|model| Request {
+            status: model.decision.into(),
+            reason: model.reason,
+        });
+    Ok(request.unwrap_or_default())
+}
+
+#[api_macro::error(internal_error, unauthorized)]
+pub enum QueueError {
+    /// Room not found
+    #[status(StatusCode::NOT_FOUND)]
+    RoomNotFound,
+    /// The music does not exist
+    #[status(StatusCode::BAD_REQUEST)]
+    MusicNotFound,
+    /// Deezer is unavailable, try again later
+    #[status(StatusCode::BAD_GATEWAY)]
+    DeezerUnavailable,
+    /// Too many musics or reason too long
+    #[status(StatusCode::BAD_REQUEST)]
+    InvalidRequest,
+    /// The order must contain every accepted music once
+    #[status(StatusCode::BAD_REQUEST)]
+    InvalidOrder,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct UpdateRequests {
+    music_ids: Vec<MusicId>,
+    status: RequestStatus,
+    /// Only kept for the rejected musics
+    reason: Option<String>,
+}
+
+impl UpdateRequests {
+    const MAX_MUSICS: usize = 100;
+    const MAX_REASON_LENGTH: usize = 200;
+
+    fn is_valid(&self) -> bool {
+        let reason_length = self.reason.as_ref().map_or(0, |r| r.chars().count());
+        !self.music_ids.is_empty()
+            && self.music_ids.len() <= Self::MAX_MUSICS
+            && reason_length <= Self::MAX_REASON_LENGTH
+    }
+}
+
+/// Change the status of several musics of the room.
+///
+/// The accepted musics are added at the end of the queue, in the given order.
+pub async fn update_requests(
+    State(state): State<ApiState>,
+    Path(room_id): Path<RoomID>,
+    user: User,
+    Json(update): Json<UpdateRequests>,
+) -> Result<(), QueueError> {
+    if user.role != Role::Admin {
+        return Err(QueueError::Unauthorized);
+    }
+
+    if !update.is_valid() {
+        return Err(QueueError::InvalidRequest);
+    }
+
+    room::Entity::find()
+        .filter(room::Column::PublicId.eq(room_id.value()))
+        .one(&state.db)
+        .await?
+        .ok_or(QueueError::RoomNotFound)?;
+
+    // A music listed twice is only updated once
+    let mut music_ids = update.music_ids;
+    let mut listed = HashSet::new();
+    music_ids.retain(|&music_id| listed.insert(music_id));
+
+    let decision = match update.status {
+        RequestStatus::Accepted => Decision::Accepted,
+        RequestStatus::Rejected => Decision::Rejected,
+        // A played music keeps its decision, back in the queue if it is unmarked
+        RequestStatus::Played => {
+            return mark_musics_played(&state, room_id, &music_ids)
+                .await
+                .map_err(|err| match err {
+                    PlayedError::InternalError(err) => QueueError::InternalError(err),
+                    PlayedError::DeezerUnavailable => QueueError::DeezerUnavailable,
+                    _ => QueueError::MusicNotFound,
+                });
+        }
+        // Pending musics are removed from the decisions
+        RequestStatus::Pending => {
+            request::Entity::delete_many()
+                .filter(request::Column::RoomId.eq(room_id.value()))
+                .filter(request::Column::MusicId.is_in(music_ids))
+                .exec(&state.db)
+                .await?;
+            return Ok(());
+        }
+    };
+
+    // Only the voted musics are stored
+    let stored = music::Entity::find()
+        .filter(music::Column::Id.is_in(music_ids.clone()))
+        .count(&state.db)
+        .await?;
+    if stored != music_ids.len() as u64 {
+        return Err(QueueError::MusicNotFound);
+    }
+
+    let txn = state.db.begin().await?;
+
+    let queue = request::Entity::find()
+        .filter(request::Column::RoomId.eq(room_id.value()))
+        .filter(request::Column::Decision.eq(Decision::Accepted))
+        .all(&txn)
+        .await?;
+    let mut next_position = queue
+        .iter()
+        .filter_map(|r| r.position)
+        .max()
+        .map_or(0, |p| p + 1);
+
+    let reason = match decision {
+        Decision::Rejected => update.reason,
+        Decision::Accepted => None,
+    };
+
+    for music_id in music_ids {
+        let position = match decision {
+            Decision::Accepted => {
+                // An accepted music keeps its place in the queue
+                let queued = queue.iter().find(|r| r.music_id == music_id);
+                match queued.and_then(|r| r.position) {
+                    Some(position) => Some(position),
+                    None => {
+                        let position = next_position;
+                        next_position += 1;
+                        Some(position)
+                    }
+                }
+            }
+            Decision::Rejected => None,
+        };
+
+        let model = request::ActiveModel {
+            room_id: Set(room_id.value()),
+            music_id: Set(music_id),
+            decision: Set(decision),
+            reason: Set(reason.clone()),
+            position: Set(position),
+            updated_at: Set(Utc::now()),
+            ..Default::default()
+        };
+
+        request::Entity::insert(model)
+            .on_conflict(
+                OnConflict::columns([request::Column::RoomId, request::Column::MusicId])
+                    .update_columns([
+                        request::Column::Decision,
+                        request::Column::Reason,
+                        request::Column::Position,
+                        request::Column::UpdatedAt,
+                    ])
+                    .to_owned(),
+            )
+            .exec(&txn)
+            .await?;
+    }
+
+    txn.commit().await?;
+
+    Ok(())
+}
+
+/// Filter out the requests of the musics played in the room, which left the queue.
+fn not_played(room_id: RoomID) -> SimpleExpr {
+    request::Column::MusicId.not_in_subquery(played_ids(room_id))
+}
+
+#[derive(Serialize, Deserialize, FromQueryResult, Debug)]
+pub struct QueuedMusic {
+    music_id: MusicId,
+    title: String,
+    artist: String,
+    preview_url: Option<String>,
+    image_hash: Option<String>,
+}
+
+/// Get the accepted musics, in the order they will be played.
+pub async fn get_queue(
+    State(state): State<ApiState>,
+    Path(room_id): Path<RoomID>,
+    user: User,
+) -> Result<Json<Vec<QueuedMusic>>, QueueError> {
+    if !has_room_access(&state.db, &user, room_id).await? {
+        return Err(QueueError::Unauthorized);
+    }
+    state.record_activity(room_id, &user);
+
+    request::Entity::find()
+        .select_only()
+        .column(request::Column::MusicId)
+        .column(music::Column::Title)
+        .column(music::Column::Artist)
+        .column(music::Column::PreviewUrl)
+        .column(music::Column::ImageHash)
+        .inner_join(music::Entity)
+        .filter(request::Column::RoomId.eq(room_id.value()))
+        .filter(request::Column::Decision.eq(Decision::Accepted))
+        .filter(not_played(room_id))
+        .order_by_asc(request::Column::Position)
+        .into_model()
+        .all(&state.db)
+        .await
+        .map(Json)
+        .map_err(From::from)
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct QueueOrder {
+    music_ids: Vec<MusicId>,
+}
+
+/// Reorder the queue, the first music being played next.
+pub async fn reorder_queue(
+    State(state): State<ApiState>,
+    Path(room_id): Path<RoomID>,
+    user: User,
+    Json(order): Json<QueueOrder>,
+) -> Result<(), QueueError> {
+    if user.role != Role::Admin {
+        return Err(QueueError::Unauthorized);
+    }
+
+    let txn = state.db.begin().await?;
+
+    let queue = request::Entity::find()
+        .filter(request::Column::RoomId.eq(room_id.value()))
+        .filter(request::Column::Decision.eq(Decision::Accepted))
+        .filter(not_played(room_id))
+        .all(&txn)
+        .await?;
+
+    let mut queued: Vec<MusicId> = queue.iter().map(|r| r.music_id).collect();
+    let mut ordered = order.music_ids.clone();
+    queued.sort_unstable();
+    ordered.sort_unstable();
+    if queued != ordered {
+        return Err(QueueError::InvalidOrder);
+    }
+
+    for (position, music_id) in order.music_ids.into_iter().enumerate() {
+        request::Entity::update_many()
+            .col_expr(request::Column::Position, Expr::value(position as u32))
+            .filter(request::Column::RoomId.eq(room_id.value()))
+            .filter(request::Column::MusicId.eq(music_id))
+            .exec(&txn)
+            .await?;
+    }
+
+    txn.commit().await?;
+
+    Ok(())
+}
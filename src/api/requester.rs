@@ -0,0 +1,163 @@
+use std::{collections::HashMap, sync::Arc};
+
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use entity::*;
+use sea_orm::{
+    prelude::*,
+    sea_query::{Alias, Expr, JoinType, Order, Query, SelectStatement},
+    FromQueryResult, QuerySelect, QueryTrait,
+};
+
+use crate::utils::{
+    jwt::{Role, User},
+    room_id::RoomID,
+};
+
+use super::{
+    event::has_room_access,
+    state::ApiState,
+    websocket::{AudienceEvent, RequestPlayingEvent},
+};
+
+/// A user of the room with the number of musics they liked and the DJ played.
+#[derive(Serialize, Deserialize, FromQueryResult, Debug)]
+pub struct Requester {
+    user: Uuid,
+    /// The nickname chosen in the room, if any
+    nickname: Option<String>,
+    played_requests: u32,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PlayedRequests {
+    played_requests: u32,
+}
+
+/// Count the played musics of the room liked by each user, the most played requesters first.
+///
+/// Only the likes of the users when the music was played count.
+fn requesters_query(room_id: RoomID) -> SelectStatement {
+    Query::select()
+        .expr_as(
+            Expr::col((played_request::Entity, played_request::Column::UserToken)),
+            Alias::new("user"),
+        )
+        .column((nickname::Entity, nickname::Column::Nickname))
+        .expr_as(
+            Expr::col((played_request::Entity, played_request::Column::MusicId)).count(),
+            Alias::new("played_requests"),
+        )
+        .from(played_request::Entity)
+        .join(
+            JoinType::LeftJoin,
+            nickname::Entity,
+            Expr::col((nickname::Entity, nickname::Column::RoomId))
+                .equals((played_request::Entity, played_request::Column::RoomId))
+                .and(
+                    Expr::col((nickname::Entity, nickname::Column::UserToken))
+                        .equals((played_request::Entity, played_request::Column::UserToken)),
+                ),
+        )
+        .and_where(
+            Expr::col((played_request::Entity, played_request::Column::RoomId)).eq(room_id.value()),
+        )
+        .group_by_col((played_request::Entity, played_request::Column::UserToken))
+        .order_by_expr(
+            Expr::col((played_request::Entity, played_request::Column::MusicId)).count(),
+            Order::Desc,
+        )
+        .take()
+}
+
+/// Notify the users who liked the music that it is playing, on the audience websocket.
+pub(super) async fn notify_requesters(
+    state: &ApiState,
+    room_id: RoomID,
+    music: &music::Model,
+) -> Result<(), DbErr> {
+    let mut statement = requesters_query(room_id);
+    let likers = played_request::Entity::find()
+        .select_only()
+        .column(played_request::Column::UserToken)
+        .filter(played_request::Column::RoomId.eq(room_id.value()))
+        .filter(played_request::Column::MusicId.eq(music.id))
+        .into_query();
+    statement.and_where(
+        Expr::col((played_request::Entity, played_request::Column::UserToken)).in_subquery(likers),
+    );
+
+    let backend = state.db.get_database_backend();
+    let requesters = Requester::find_by_statement(backend.build(&statement))
+        .all(&state.db)
+        .await?;
+    if requesters.is_empty() {
+        return Ok(());
+    }
+
+    let requesters: HashMap<Uuid, u32> = requesters
+        .into_iter()
+        .map(|requester| (requester.user, requester.played_requests))
+        .collect();
+    let event = AudienceEvent::RequestPlaying(RequestPlayingEvent {
+        music_id: music.id,
+        title: music.title.clone(),
+        artist: music.artist.clone(),
+        requesters: Arc::new(requesters),
+    });
+    state.audience_channels.send(room_id, event);
+
+    Ok(())
+}
+
+#[api_macro::error(internal_error, unauthorized)]
+pub enum RequesterError {}
+
+/// Get the users of the room whose liked musics were played, the most played first.
+pub async fn get_requesters(
+    State(state): State<ApiState>,
+    Path(room_id): Path<RoomID>,
+    user: User,
+) -> Result<Json<Vec<Requester>>, RequesterError> {
+    if user.role != Role::Admin {
+        return Err(RequesterError::Unauthorized);
+    }
+
+    let statement = requesters_query(room_id);
+    let backend = state.db.get_database_backend();
+    let requesters = Requester::find_by_statement(backend.build(&statement))
+        .all(&state.db)
+        .await?;
+
+    Ok(Json(requesters))
+}
+
+/// Get the number of musics liked by the user and played in the room.
+pub async fn get_played_requests(
+    State(state): State<ApiState>,
+    Path(room_id): Path<RoomID>,
+    user: User,
+) -> Result<Json<PlayedRequests>, RequesterError> {
+    if !has_room_access(&state.db, &user, room_id).await? {
+        return Err(RequesterError::Unauthorized);
+    }
+    state.record_activity(room_id, &user);
+
+    let mut statement = requesters_query(room_id);
+    statement.and_where(
+        Expr::col((played_request::Entity, played_request::Column::UserToken)).eq(user.uid),
+    );
+
+    let backend = state.db.get_database_backend();
+    let played_requests = Requester::find_by_statement(backend.build(&statement))
+        .one(&state.db)
+        .await?
+        .map_or(0, |requester| requester.played_requests);
+
+    Ok(Json(PlayedRequests { played_requests }))
+}
@@ -1,7 +1,12 @@
-use std::time::Duration;
+use std::{
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+    net::IpAddr,
+    time::Duration,
+};
 
 use axum::{
-    extract::{Path, State},
+    extract::{self, Path, State},
     http::StatusCode,
     Json,
 };
@@ -12,16 +17,46 @@ use uuid::Uuid;
 
 use crate::utils::jwt::{Role, User, UserToken};
 
-use entity::*;
-use sea_orm::{prelude::*, FromQueryResult, JoinType, QuerySelect, QueryTrait, Set};
+use entity::{request::Decision, room_settings::RankingAlgorithm, vote::VoteValue, *};
+use sea_orm::{
+    prelude::*, Condition, ConnectionTrait, DatabaseTransaction, FromQueryResult, JoinType,
+    QuerySelect, QueryTrait, Set, TransactionTrait,
+};
+
+use sea_orm::sea_query::{
+    Alias, ColumnRef, Expr, Func, LikeExpr, OnConflict, Order, Query, SelectStatement, SimpleExpr,
+};
 
-use sea_orm::sea_query::{Alias, Expr, Order, Query};
+use crate::utils::{
+    blocklist::Blocklist,
+    client_ip::ClientIp,
+    cursor,
+    fetch::fetch_unique,
+    nickname::clean_nickname,
+    profanity::contains_profanity,
+    ranking::{RankedVote, Ranking},
+    room_id::RoomID,
+};
 
-use crate::utils::room_id::RoomID;
+use super::{
+    admin::is_duplicate_key,
+    blocklist::room_blocklist,
+    dedication::{save_dedication, Dedication, MAX_MESSAGE_LENGTH},
+    event::has_room_access,
+    nickname::{is_nickname_taken, save_nickname},
+    played::{played_ids, played_musics},
+    queue::{music_request, room_requests, Request, RequestStatus},
+    search::{get_music_or_store_music, MusicError},
+    settings::{room_settings, RoomSettings},
+    state::ApiState,
+    websocket::{AbuseAlert, RoomEvent, VoteEvent},
+    MusicId,
+};
 
-use super::{search::get_music_or_store_music, state::ApiState, websocket::VoteEvent, MusicId};
+/// The maximum number of active users in a room.
+const MAX_ROOM_USERS: usize = 1000;
 
-#[api_macro::error(internal_error)]
+#[api_macro::error(internal_error, unauthorized)]
 #[default_status(StatusCode::UNAUTHORIZED)]
 pub enum JoinError {
     /// The room does not exist
@@ -31,11 +66,27 @@ pub enum JoinError {
     RoomFull,
     /// The room is closed
     RoomExpired,
+    /// The nickname must have 2 to 24 letters, digits, spaces, `-`, `_` or `.`
+    #[status(StatusCode::BAD_REQUEST)]
+    InvalidNickname,
+    /// The nickname is not allowed
+    #[status(StatusCode::BAD_REQUEST)]
+    NicknameRefused,
+    /// The nickname is already used in the room
+    #[status(StatusCode::CONFLICT)]
+    NicknameTaken,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct JoinQuery {
+    /// The nickname shown to the DJ, anonymous if `None`
+    nickname: Option<String>,
 }
 
 pub async fn join(
     State(state): State<ApiState>,
     Path(room_id): Path<RoomID>,
+    extract::Query(query): extract::Query<JoinQuery>,
 ) -> Result<Json<UserToken>, JoinError> {
     // the sleep is to prevent brut force to find a random room to spam
     // is it really useful and effective (maybe?)
@@ -56,26 +107,52 @@ pub async fn join(
         return Err(JoinError::RoomExpired);
     }
 
+    if state.presence.active_users(room_id) >= MAX_ROOM_USERS {
+        return Err(JoinError::RoomFull);
+    }
+
+    let user = User::new_user(room_id);
+    let nickname = match query.nickname {
+        Some(nickname) => Some(check_nickname(&state, room_id, &user, &nickname).await?),
+        None => None,
+    };
+
+    // The join is only counted if the nickname is saved
+    let txn = state.db.begin().await?;
+
     let row_affected = room::Entity::update_many()
         .col_expr(
-            room::Column::UserCount,
-            Expr::add(Expr::col(room::Column::UserCount), 1),
+            room::Column::JoinCount,
+            Expr::add(Expr::col(room::Column::JoinCount), 1),
         )
         // The filtering assume that the public id is unique
         .filter(room::Column::PublicId.eq(room_id.value()))
-        .filter(room::Column::UserCount.lt(1000))
-        .exec(&state.db)
+        .exec(&txn)
         .await?
         .rows_affected;
 
     match row_affected {
-        0 => Err(JoinError::RoomFull),
-        1 => Ok(Json(
-            User::new_user(room_id).into_token(room.expiration_date),
-        )),
+        0 => Err(JoinError::RoomNotFound),
+        1 => {
+            if let Some(nickname) = nickname {
+                // Another user may have taken the nickname since the check
+                save_nickname(&txn, room_id, user.uid, nickname)
+                    .await
+                    .map_err(|err| {
+                        if is_duplicate_key(&err) {
+                            return JoinError::NicknameTaken;
+                        }
+                        JoinError::InternalError(err)
+                    })?;
+            }
+            txn.commit().await?;
+
+            state.record_activity(room_id, &user);
+            Ok(Json(user.into_token(room.expiration_date)))
+        }
         _ => {
             log::error!(
-                "More than one Room was update ({}): User join -> user_count + 1",
+                "More than one Room was update ({}): User join -> join_count + 1",
                 row_affected
             );
             Err(JoinError::RoomNotFound)
@@ -83,76 +160,632 @@ pub async fn join(
     }
 }
 
+#[derive(Serialize, Deserialize, Debug)]
+pub struct NicknameBody {
+    nickname: String,
+}
+
+/// Choose the nickname of the user in the room, replacing the previous one.
+///
+/// The event users, which don't join the rooms, and the users whose nickname was removed by the DJ
+/// get one this way.
+pub async fn set_nickname(
+    State(state): State<ApiState>,
+    Path(room_id): Path<RoomID>,
+    user: User,
+    Json(body): Json<NicknameBody>,
+) -> Result<(), JoinError> {
+    if user.role == Role::Admin || !has_room_access(&state.db, &user, room_id).await? {
+        return Err(JoinError::Unauthorized);
+    }
+    state.record_activity(room_id, &user);
+
+    let nickname = check_nickname(&state, room_id, &user, &body.nickname).await?;
+
+    let txn = state.db.begin().await?;
+    nickname::Entity::delete_by_id((room_id.value(), user.uid))
+        .exec(&txn)
+        .await?;
+    // Another user may have taken the nickname since the check
+    save_nickname(&txn, room_id, user.uid, nickname)
+        .await
+        .map_err(|err| {
+            if is_duplicate_key(&err) {
+                return JoinError::NicknameTaken;
+            }
+            JoinError::InternalError(err)
+        })?;
+    txn.commit().await?;
+
+    Ok(())
+}
+
+/// Clean the nickname and check that it is allowed and free in the room.
+async fn check_nickname(
+    state: &ApiState,
+    room_id: RoomID,
+    user: &User,
+    nickname: &str,
+) -> Result<String, JoinError> {
+    let nickname = clean_nickname(nickname).ok_or(JoinError::InvalidNickname)?;
+
+    let blocklist = room_blocklist(&state.db, room_id).await?;
+    if contains_profanity(&nickname) || blocklist.blocks_nickname(&nickname) {
+        return Err(JoinError::NicknameRefused);
+    }
+
+    if is_nickname_taken(&state.db, room_id, user.uid, &nickname).await? {
+        return Err(JoinError::NicknameTaken);
+    }
+
+    Ok(nickname)
+}
+
+#[api_macro::error(internal_error, unauthorized)]
+pub enum HeartbeatError {}
+
+/// Keep the user active in the room.
+pub async fn heartbeat(
+    State(state): State<ApiState>,
+    Path(room_id): Path<RoomID>,
+    user: User,
+) -> Result<(), HeartbeatError> {
+    if !has_room_access(&state.db, &user, room_id).await? {
+        return Err(HeartbeatError::Unauthorized);
+    }
+
+    state.record_activity(room_id, &user);
+
+    Ok(())
+}
+
 #[api_macro::error(internal_error, unauthorized)]
 pub enum VoteError {
     /// The music does not exist
     #[status(StatusCode::BAD_REQUEST)]
     MusicNotFound,
+    /// Deezer is unavailable, try again later
+    #[status(StatusCode::BAD_GATEWAY)]
+    DeezerUnavailable,
     /// Already voted for the music.
     #[status(StatusCode::BAD_REQUEST)]
     AlreadyVoted,
+    /// No vote to retract for the music
+    #[status(StatusCode::BAD_REQUEST)]
+    NotVoted,
+    /// Dislikes are disabled in this room
+    #[status(StatusCode::FORBIDDEN)]
+    DislikesDisabled,
+    /// No vote left, remove a like before voting again
+    #[status(StatusCode::FORBIDDEN)]
+    BudgetExhausted,
+    /// Wait a bit before voting again
+    #[status(StatusCode::TOO_MANY_REQUESTS)]
+    Cooldown,
+    /// The music is blocked in this room
+    #[status(StatusCode::FORBIDDEN)]
+    Blocked,
+    /// Musics with explicit lyrics are not allowed in this room
+    #[status(StatusCode::FORBIDDEN)]
+    Explicit,
+    /// Too many votes, slow down
+    #[status(StatusCode::TOO_MANY_REQUESTS)]
+    RateLimited,
+    /// A batch must contain between 1 and 20 votes
+    #[status(StatusCode::BAD_REQUEST)]
+    InvalidBatch,
+    /// A dedication must come with a like and have at most 140 characters
+    #[status(StatusCode::BAD_REQUEST)]
+    InvalidMessage,
+    /// The dedication contains a forbidden word
+    #[status(StatusCode::BAD_REQUEST)]
+    Profanity,
+}
+
+#[api_macro::error(unauthorized)]
+pub enum AbuseAlertsError {}
+
+/// Get the abuse alerts of the room, the most recent first.
+pub async fn get_abuse_alerts(
+    State(state): State<ApiState>,
+    Path(room_id): Path<RoomID>,
+    user: User,
+) -> Result<Json<Vec<AbuseAlert>>, AbuseAlertsError> {
+    if user.role != Role::Admin {
+        return Err(AbuseAlertsError::Unauthorized);
+    }
+
+    Ok(Json(state.vote_guard.alerts(room_id)))
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct VoteBody {
     music_id: MusicId,
     like: bool,
+    /// A short dedication for the DJ, only allowed with a like
+    message: Option<String>,
 }
 
 impl VoteBody {
-    fn to_active_model(&self, user_token: Uuid, room_id: u32) -> vote::ActiveModel {
-        vote::ActiveModel {
-            user_token: Set(user_token),
-            room_id: Set(room_id),
-            music_id: Set(self.music_id),
-            like: Set(self.like),
-            ..Default::default()
+    fn value(&self) -> VoteValue {
+        match self.like {
+            true => VoteValue::Like,
+            false => VoteValue::Dislike,
         }
     }
+
+    /// The trimmed dedication of the vote, if any.
+    fn message(&self) -> Result<Option<String>, VoteError> {
+        let message = self.message.as_deref().map(str::trim).unwrap_or_default();
+        if message.is_empty() {
+            return Ok(None);
+        }
+
+        if !self.like || message.chars().count() > MAX_MESSAGE_LENGTH {
+            return Err(VoteError::InvalidMessage);
+        }
+        if contains_profanity(message) {
+            return Err(VoteError::Profanity);
+        }
+        Ok(Some(message.to_string()))
+    }
 }
 
 pub async fn vote(
     State(state): State<ApiState>,
     Path(room_id): Path<RoomID>,
+    ClientIp(ip): ClientIp,
     user: User,
     Json(vote): Json<VoteBody>,
-) -> Result<(), VoteError> {
-    if (Role::User { room_id }) != user.role && user.role != Role::Admin {
+) -> Result<Json<VoteResponse>, VoteError> {
+    if !has_room_access(&state.db, &user, room_id).await? {
         return Err(VoteError::Unauthorized);
     }
+    state.record_activity(room_id, &user);
 
-    let music = get_music_or_store_music(&state, vote.music_id)
-        .await
-        .map_err(|e| {
-            log::error!("Failed to get music: {}", e);
-            VoteError::MusicNotFound
-        })?;
+    // Checked before fetching the music, which may call Deezer
+    if !state.vote_guard.try_vote(user.uid, ip) {
+        return Err(VoteError::RateLimited);
+    }
 
-    let last_vote = vote::Entity::find()
-        .column_as(vote::Column::VoteDate.max(), vote::Column::VoteDate)
-        .filter(vote::Column::MusicId.eq(music.id))
-        .filter(vote::Column::UserToken.eq(user.uid))
-        .group_by(vote::Column::UserToken)
-        .one(&state.db)
-        .await?
-        .map(|model| model.like);
+    let blocklist = room_blocklist(&state.db, room_id).await?;
+    let musics = load_musics(&state, [vote.music_id]).await?;
+
+    let txn = begin_write(&state.db).await?;
+    let mut ballot = Ballot::load(&txn, room_id, user.uid).await?;
+    ballot.check_cooldown(vote.value())?;
+    let applied = apply_vote(&txn, &mut ballot, &blocklist, &musics, &vote).await?;
+    txn.commit().await?;
+
+    send_vote(&state, room_id, &user, ip, applied);
 
-    if last_vote == Some(vote.like) {
-        return Err(VoteError::AlreadyVoted);
+    Ok(Json(VoteResponse {
+        remaining_votes: ballot.budget().remaining_votes,
+    }))
+}
+
+/// Retract the vote of the user for a music, going back to neutral.
+pub async fn retract_vote(
+    State(state): State<ApiState>,
+    Path((room_id, music_id)): Path<(RoomID, MusicId)>,
+    ClientIp(ip): ClientIp,
+    user: User,
+) -> Result<Json<VoteResponse>, VoteError> {
+    if !has_room_access(&state.db, &user, room_id).await? {
+        return Err(VoteError::Unauthorized);
     }
+    state.record_activity(room_id, &user);
 
-    vote.to_active_model(user.uid, room_id.value())
-        .save(&state.db)
-        .await?;
+    if !state.vote_guard.try_vote(user.uid, ip) {
+        return Err(VoteError::RateLimited);
+    }
 
-    let event = VoteEvent {
-        music_id: music.id,
-        like: vote.like,
+    let txn = begin_write(&state.db).await?;
+    let mut ballot = Ballot::load(&txn, room_id, user.uid).await?;
+    let event = ballot.apply(&txn, music_id, VoteValue::Neutral).await?;
+    txn.commit().await?;
+
+    let applied = AppliedVote {
+        event,
+        dedication: None,
     };
+    send_vote(&state, room_id, &user, ip, applied);
 
+    Ok(Json(VoteResponse {
+        remaining_votes: ballot.budget().remaining_votes,
+    }))
+}
+
+/// The maximum number of votes in a batch.
+const MAX_BATCH_VOTES: usize = 20;
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct BatchVoteResult {
+    music_id: MusicId,
+    /// The status code the vote would have had alone
+    status: u16,
+    /// Why the vote was refused, `None` if it was applied
+    error: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct BatchVoteResponse {
+    /// The result of each vote, in the order of the batch
+    results: Vec<BatchVoteResult>,
+    remaining_votes: Option<u32>,
+}
+
+/// Apply several votes at once, the votes refused being skipped.
+pub async fn batch_vote(
+    State(state): State<ApiState>,
+    Path(room_id): Path<RoomID>,
+    ClientIp(ip): ClientIp,
+    user: User,
+    Json(votes): Json<Vec<VoteBody>>,
+) -> Result<Json<BatchVoteResponse>, VoteError> {
+    if !has_room_access(&state.db, &user, room_id).await? {
+        return Err(VoteError::Unauthorized);
+    }
+    state.record_activity(room_id, &user);
+
+    if votes.is_empty() || votes.len() > MAX_BATCH_VOTES {
+        return Err(VoteError::InvalidBatch);
+    }
+
+    let blocklist = room_blocklist(&state.db, room_id).await?;
+
+    // Only the musics of the votes under the rate limits are fetched
+    let allowed: Vec<bool> = votes
+        .iter()
+        .map(|_| state.vote_guard.try_vote(user.uid, ip))
+        .collect();
+    let music_ids = votes
+        .iter()
+        .zip(&allowed)
+        .filter(|(_, &allowed)| allowed)
+        .map(|(vote, _)| vote.music_id);
+    let musics = load_musics(&state, music_ids).await?;
+
+    let txn = begin_write(&state.db).await?;
+    let mut ballot = Ballot::load(&txn, room_id, user.uid).await?;
+    // The batch counts as a single vote for the cooldown
+    ballot.check_cooldown(VoteValue::Like)?;
+    let mut results = Vec::with_capacity(votes.len());
+    let mut votes_applied = Vec::new();
+
+    for (vote, allowed) in votes.into_iter().zip(allowed) {
+        let applied = match allowed {
+            false => Err(VoteError::RateLimited),
+            true => apply_vote(&txn, &mut ballot, &blocklist, &musics, &vote).await,
+        };
+
+        let result = match applied {
+            Ok(applied) => {
+                votes_applied.push(applied);
+                BatchVoteResult {
+                    music_id: vote.music_id,
+                    status: StatusCode::OK.as_u16(),
+                    error: None,
+                }
+            }
+            // A database error cancels the whole batch
+            Err(err @ VoteError::InternalError(_)) => return Err(err),
+            Err(err) => BatchVoteResult {
+                music_id: vote.music_id,
+                status: err.status().as_u16(),
+                error: Some(err.to_string()),
+            },
+        };
+        results.push(result);
+    }
+
+    txn.commit().await?;
+
+    for applied in votes_applied {
+        send_vote(&state, room_id, &user, ip, applied);
+    }
+
+    Ok(Json(BatchVoteResponse {
+        results,
+        remaining_votes: ballot.budget().remaining_votes,
+    }))
+}
+
+/// The musics of the votes, or the Deezer error if they could not be fetched.
+type LoadedMusics = HashMap<MusicId, Result<music::Model, deezer_rs::Error>>;
+
+/// Get the musics of the votes, storing them first if needed.
+///
+/// It must be done before the vote transaction, as Deezer may be called for each music
+/// and nobody could vote while waiting for it.
+async fn load_musics(
+    state: &ApiState,
+    music_ids: impl IntoIterator<Item = MusicId>,
+) -> Result<LoadedMusics, DbErr> {
+    let state = state.clone();
+    let fetch = move |music_id| {
+        let state = state.clone();
+        async move { get_music_or_store_music(&state, music_id).await }
+    };
+
+    let mut musics = HashMap::new();
+    for (music_id, music) in fetch_unique(music_ids, fetch).await {
+        let music = match music {
+            Ok(music) => Ok(music),
+            Err(MusicError::Deezer(e)) => Err(e),
+            Err(MusicError::Database(e)) => return Err(e),
+        };
+        musics.insert(music_id, music);
+    }
+    Ok(musics)
+}
+
+/// Check the music can be voted in the room.
+fn check_music(
+    settings: &RoomSettings,
+    blocklist: &Blocklist,
+    musics: &LoadedMusics,
+    music_id: MusicId,
+) -> Result<MusicId, VoteError> {
+    let music = match musics.get(&music_id) {
+        Some(Ok(music)) => music,
+        Some(Err(_)) => return Err(VoteError::DeezerUnavailable),
+        None => return Err(VoteError::MusicNotFound),
+    };
+
+    if blocklist.blocks(music.id, &music.title, &music.artist) {
+        return Err(VoteError::Blocked);
+    }
+
+    // A music whose flag is unknown may be explicit
+    if music.explicit != Some(false) && !settings.allow_explicit {
+        return Err(VoteError::Explicit);
+    }
+
+    Ok(music.id)
+}
+
+/// A vote saved, with its dedication if any.
+struct AppliedVote {
+    event: VoteEvent,
+    dedication: Option<Dedication>,
+}
+
+/// Check the vote and its dedication, then save them.
+async fn apply_vote(
+    txn: &DatabaseTransaction,
+    ballot: &mut Ballot,
+    blocklist: &Blocklist,
+    musics: &LoadedMusics,
+    vote: &VoteBody,
+) -> Result<AppliedVote, VoteError> {
+    let message = vote.message()?;
+    let music_id = check_music(&ballot.settings, blocklist, musics, vote.music_id)?;
+    let event = ballot.apply(txn, music_id, vote.value()).await?;
+
+    let dedication = match message {
+        Some(message) => {
+            Some(save_dedication(txn, ballot.room_id, ballot.user_token, music_id, message).await?)
+        }
+        None => None,
+    };
+
+    Ok(AppliedVote { event, dedication })
+}
+
+/// Send the vote and its dedication to the DJ, with an alert if it looks like an abuse.
+fn send_vote(state: &ApiState, room_id: RoomID, user: &User, ip: IpAddr, applied: AppliedVote) {
+    let AppliedVote { event, dedication } = applied;
     state.rooms_channels.send_vote(room_id, event);
+    if let Some(dedication) = dedication {
+        state
+            .rooms_channels
+            .send(room_id, RoomEvent::DedicationPending(dedication));
+    }
 
-    Ok(())
+    if event.value != VoteValue::Neutral {
+        if let Some(alert) = state
+            .vote_guard
+            .record(room_id, user.uid, ip, event.music_id)
+        {
+            state.rooms_channels.send(room_id, RoomEvent::Abuse(alert));
+        }
+    }
+}
+
+/// Begin a transaction holding the write lock of the database, like `BEGIN IMMEDIATE`.
+///
+/// SQLite only takes the lock on the first write, so two transactions could read the same votes
+/// and both go over the budget of the user, the no-op update taking it at once.
+pub(super) async fn begin_write(db: &DatabaseConnection) -> Result<DatabaseTransaction, DbErr> {
+    let txn = db.begin().await?;
+    txn.execute_unprepared("UPDATE current_vote SET value = value WHERE 0")
+        .await?;
+    Ok(txn)
+}
+
+/// The votes of a user in a room, updated as the new votes are applied.
+struct Ballot {
+    room_id: RoomID,
+    user_token: Uuid,
+    settings: RoomSettings,
+    user_votes: Vec<VotedMusic>,
+    played: HashSet<MusicId>,
+}
+
+impl Ballot {
+    /// Load the votes of the user, in the transaction of the new votes so they can't be outdated.
+    async fn load(
+        db: &impl ConnectionTrait,
+        room_id: RoomID,
+        user_token: Uuid,
+    ) -> Result<Self, DbErr> {
+        Ok(Self {
+            room_id,
+            user_token,
+            settings: room_settings(db, room_id).await?,
+            user_votes: user_votes(db, room_id, user_token).await?,
+            played: played_musics(db, room_id).await?,
+        })
+    }
+
+    fn budget(&self) -> VoteBudget {
+        VoteBudget::new(self.settings.max_upvotes, &self.user_votes, &self.played)
+    }
+
+    /// Check the cooldown since the last vote of the user, retracting a vote being always allowed.
+    fn check_cooldown(&self, value: VoteValue) -> Result<(), VoteError> {
+        if value == VoteValue::Neutral {
+            return Ok(());
+        }
+
+        let cooldown = Duration::from_secs(self.settings.vote_cooldown.into());
+        if let Some(last_vote_date) = self.user_votes.iter().map(|voted| voted.vote_date).max() {
+            let elapsed = (Utc::now() - last_vote_date).to_std().unwrap_or_default();
+            if elapsed < cooldown {
+                return Err(VoteError::Cooldown);
+            }
+        }
+        Ok(())
+    }
+
+    /// Check the room rules and save the new vote of the user for the music.
+    async fn apply(
+        &mut self,
+        txn: &DatabaseTransaction,
+        music_id: MusicId,
+        value: VoteValue,
+    ) -> Result<VoteEvent, VoteError> {
+        let previous = self
+            .user_votes
+            .iter()
+            .find(|voted| voted.music_id == music_id)
+            .map_or(VoteValue::Neutral, |voted| voted.value);
+
+        match (previous, value) {
+            (VoteValue::Neutral, VoteValue::Neutral) => return Err(VoteError::NotVoted),
+            (previous, value) if previous == value => return Err(VoteError::AlreadyVoted),
+            _ => {}
+        }
+
+        if value == VoteValue::Dislike && !self.settings.allow_dislikes {
+            return Err(VoteError::DislikesDisabled);
+        }
+
+        // The votes for a played music are free
+        let counted = !self.played.contains(&music_id);
+        if counted && value == VoteValue::Like && self.budget().remaining_votes == Some(0) {
+            return Err(VoteError::BudgetExhausted);
+        }
+
+        let vote_date = Utc::now();
+        let current = current_vote::ActiveModel {
+            room_id: Set(self.room_id.value()),
+            user_token: Set(self.user_token),
+            music_id: Set(music_id),
+            value: Set(value),
+            vote_date: Set(vote_date),
+        };
+
+        // The vote is only replaced if it did not change since it was read,
+        // two concurrent votes of the user can't both be applied
+        let updated = current_vote::Entity::insert(current)
+            .on_conflict(
+                OnConflict::columns([
+                    current_vote::Column::RoomId,
+                    current_vote::Column::UserToken,
+                    current_vote::Column::MusicId,
+                ])
+                .update_columns([current_vote::Column::Value, current_vote::Column::VoteDate])
+                .action_and_where(
+                    Expr::col((current_vote::Entity, current_vote::Column::Value))
+                        .eq(previous.to_value()),
+                )
+                .to_owned(),
+            )
+            .exec_without_returning(txn)
+            .await?;
+        if updated == 0 {
+            return Err(VoteError::AlreadyVoted);
+        }
+
+        // Every vote is kept in the log
+        vote::ActiveModel {
+            user_token: Set(self.user_token),
+            room_id: Set(self.room_id.value()),
+            music_id: Set(music_id),
+            value: Set(value),
+            vote_date: Set(vote_date),
+            ..Default::default()
+        }
+        .insert(txn)
+        .await?;
+
+        let voted = VotedMusic {
+            music_id,
+            vote_date,
+            value,
+        };
+        match self.user_votes.iter_mut().find(|v| v.music_id == music_id) {
+            Some(previous) => *previous = voted,
+            None => self.user_votes.push(voted),
+        }
+
+        Ok(VoteEvent {
+            music_id,
+            value,
+            previous,
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct VoteResponse {
+    remaining_votes: Option<u32>,
+}
+
+/// The number of likes a user can give in a room.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct VoteBudget {
+    /// Maximum number of musics liked at the same time (no limit if `None`)
+    max_upvotes: Option<u32>,
+    /// Number of likes left (no limit if `None`)
+    remaining_votes: Option<u32>,
+}
+
+impl VoteBudget {
+    /// The likes of the played musics are given back.
+    fn new(max_upvotes: Option<u32>, user_votes: &[VotedMusic], played: &HashSet<MusicId>) -> Self {
+        let upvotes = user_votes
+            .iter()
+            .filter(|voted| voted.value == VoteValue::Like && !played.contains(&voted.music_id))
+            .count() as u32;
+        Self {
+            max_upvotes,
+            remaining_votes: max_upvotes.map(|max| max.saturating_sub(upvotes)),
+        }
+    }
+}
+
+pub async fn get_vote_budget(
+    State(state): State<ApiState>,
+    Path(room_id): Path<RoomID>,
+    user: User,
+) -> Result<Json<VoteBudget>, GetVotedMusicError> {
+    if !has_room_access(&state.db, &user, room_id).await? {
+        return Err(GetVotedMusicError::Unauthorized);
+    }
+    state.record_activity(room_id, &user);
+
+    let settings = room_settings(&state.db, room_id).await?;
+    let user_votes = user_votes(&state.db, room_id, user.uid).await?;
+    let played = played_musics(&state.db, room_id).await?;
+
+    Ok(Json(VoteBudget::new(
+        settings.max_upvotes,
+        &user_votes,
+        &played,
+    )))
 }
 
 #[derive(Serialize, Deserialize, FromQueryResult, Debug)]
@@ -162,7 +795,10 @@ pub struct Music {
     artist: String,
     preview_url: Option<String>,
     image_hash: Option<String>,
-    votes: u32,
+    /// The net score, likes minus dislikes
+    votes: i32,
+    likes: u32,
+    dislikes: u32,
 }
 
 #[api_macro::error(internal_error, unauthorized)]
@@ -173,16 +809,283 @@ pub enum GetMusicError {
     /// Room not found
     #[status(StatusCode::BAD_REQUEST)]
     RoomNotFound,
+    /// The window is too long
+    #[status(StatusCode::BAD_REQUEST)]
+    InvalidWindow,
+    /// The limit must be between 1 and 500
+    #[status(StatusCode::BAD_REQUEST)]
+    InvalidLimit,
+    /// Invalid cursor
+    #[status(StatusCode::BAD_REQUEST)]
+    InvalidCursor,
+}
+
+/// Only count the votes cast since a date, or during the last minutes.
+///
+/// The latest start is used if both are set.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy)]
+pub struct RankingWindow {
+    pub since: Option<DateTimeUtc>,
+    /// Length of the window in minutes
+    pub window: Option<u32>,
+}
+
+impl RankingWindow {
+    pub const MAX_WINDOW: u32 = 24 * 60;
+
+    pub fn is_valid(&self) -> bool {
+        !matches!(self.window, Some(window) if window > Self::MAX_WINDOW)
+    }
+
+    /// The date of the first vote counted, if any.
+    fn start(&self, now: DateTimeUtc) -> Option<DateTimeUtc> {
+        let window_start = self
+            .window
+            .map(|window| now - chrono::Duration::minutes(window.into()));
+        self.since.max(window_start)
+    }
+}
+
+/// A music of the ranking with its score for the ranking algorithm of the room.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RankedMusic {
+    #[serde(flatten)]
+    music: Music,
+    score: f64,
+    /// The date of the last vote counted
+    last_vote: Option<DateTimeUtc>,
+    #[serde(flatten)]
+    request: Request,
+}
+
+/// The order of a page of the ranking.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RankingSort {
+    /// The best score first
+    #[default]
+    Score,
+    /// The last voted first
+    Recency,
+    /// By title, alphabetically
+    Title,
+}
+
+impl RankingSort {
+    /// Compare two musics in the order of the page, the smallest id winning the ties.
+    fn compare(self, a: &RankingCursor, b: &RankingCursor) -> Ordering {
+        let order = match self {
+            RankingSort::Score => b
+                .score
+                .partial_cmp(&a.score)
+                .unwrap_or(Ordering::Equal)
+                .then(b.votes.cmp(&a.votes))
+                .then(b.likes.cmp(&a.likes)),
+            RankingSort::Recency => b.last_vote.cmp(&a.last_vote),
+            RankingSort::Title => a.title.to_lowercase().cmp(&b.title.to_lowercase()),
+        };
+        order.then(a.music_id.cmp(&b.music_id))
+    }
+
+    /// The columns of the net score ranking in the order of the page, the smallest id winning the ties.
+    fn columns(self) -> Vec<(SimpleExpr, Order)> {
+        let mut columns = match self {
+            RankingSort::Score => vec![
+                (Expr::col(Alias::new("votes")).into(), Order::Desc),
+                (Expr::col(Alias::new("likes")).into(), Order::Desc),
+            ],
+            RankingSort::Recency => vec![(Expr::col(Alias::new("last_vote")).into(), Order::Desc)],
+            RankingSort::Title => vec![(
+                Func::lower(Expr::col(music::Column::Title)).into(),
+                Order::Asc,
+            )],
+        };
+        columns.push((Expr::col(music::Column::Id).into(), Order::Asc));
+        columns
+    }
+
+    /// The values of the `columns` for the music of the cursor.
+    fn values(self, cursor: &RankingCursor) -> Vec<SimpleExpr> {
+        let mut values = match self {
+            RankingSort::Score => vec![cursor.votes.into(), cursor.likes.into()],
+            RankingSort::Recency => vec![cursor.last_vote.into()],
+            RankingSort::Title => vec![Func::lower(Expr::val(cursor.title.clone())).into()],
+        };
+        values.push(cursor.music_id.into());
+        values
+    }
+}
+
+/// The sort keys of the last music of a page, the next page starting after it.
+#[derive(Serialize, Deserialize, Debug)]
+struct RankingCursor {
+    score: f64,
+    votes: i32,
+    likes: u32,
+    last_vote: Option<DateTimeUtc>,
+    title: String,
+    music_id: MusicId,
+}
+
+impl RankingCursor {
+    fn new(ranked: &RankedMusic) -> Self {
+        RankingCursor {
+            score: ranked.score,
+            votes: ranked.music.votes,
+            likes: ranked.music.likes,
+            last_vote: ranked.last_vote,
+            title: ranked.music.title.clone(),
+            music_id: ranked.music.id,
+        }
+    }
+}
+
+/// The options of a page of the ranking.
+///
+/// The window is not flattened as the query parser can't read numbers in flattened structs.
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct RankingQuery {
+    since: Option<DateTimeUtc>,
+    window: Option<u32>,
+    /// The maximum number of musics of the page
+    limit: Option<u32>,
+    /// The `next_cursor` of the previous page.
+    ///
+    /// A music moving across the cursor as votes are cast is skipped or seen twice.
+    cursor: Option<String>,
+    #[serde(default)]
+    sort: RankingSort,
+    /// Only the musics of the artists containing this text, ignoring the case
+    artist: Option<String>,
+    status: Option<RequestStatus>,
+}
+
+impl RankingQuery {
+    const DEFAULT_LIMIT: u32 = 100;
+    const MAX_LIMIT: u32 = 500;
+
+    fn window(&self) -> RankingWindow {
+        RankingWindow {
+            since: self.since,
+            window: self.window,
+        }
+    }
+
+    fn matches(&self, ranked: &RankedMusic) -> bool {
+        let artist_matches = match &self.artist {
+            Some(artist) => ranked
+                .music
+                .artist
+                .to_lowercase()
+                .contains(&artist.trim().to_lowercase()),
+            None => true,
+        };
+        let status_matches = match self.status {
+            Some(status) => ranked.request.status == status,
+            None => true,
+        };
+        artist_matches && status_matches
+    }
+
+    /// The filters of `matches` on the columns of the ranking query.
+    fn condition(&self, room_id: RoomID) -> Condition {
+        let mut condition = Condition::all();
+        if let Some(artist) = &self.artist {
+            let artist = artist
+                .trim()
+                .to_lowercase()
+                .replace('\\', "\\\\")
+                .replace('%', "\\%")
+                .replace('_', "\\_");
+            let pattern = LikeExpr::new(format!("%{}%", artist)).escape('\\');
+            condition = condition
+                .add(Expr::expr(Func::lower(Expr::col(music::Column::Artist))).like(pattern));
+        }
+        if let Some(status) = self.status {
+            condition = condition.add(status_filter(room_id, status));
+        }
+        condition
+    }
+}
+
+/// Only keep the musics having the status, like `room_requests`.
+fn status_filter(room_id: RoomID, status: RequestStatus) -> SimpleExpr {
+    let id = Expr::col(music::Column::Id);
+    let requests = |decision: Option<Decision>| {
+        let mut requests = request::Entity::find()
+            .select_only()
+            .column(request::Column::MusicId)
+            .filter(request::Column::RoomId.eq(room_id.value()));
+        if let Some(decision) = decision {
+            requests = requests.filter(request::Column::Decision.eq(decision));
+        }
+        requests.into_query()
+    };
+
+    // A played music is played whatever the decision of the DJ
+    let not_played = id.clone().not_in_subquery(played_ids(room_id));
+    match status {
+        RequestStatus::Played => id.in_subquery(played_ids(room_id)),
+        RequestStatus::Pending => not_played.and(id.not_in_subquery(requests(None))),
+        RequestStatus::Accepted => {
+            not_played.and(id.in_subquery(requests(Some(Decision::Accepted))))
+        }
+        RequestStatus::Rejected => {
+            not_played.and(id.in_subquery(requests(Some(Decision::Rejected))))
+        }
+    }
+}
+
+/// Only keep the rows after the cursor, or before it, the columns being compared in order.
+fn beyond_cursor(sort: RankingSort, cursor: &RankingCursor, after: bool) -> Condition {
+    let mut beyond = Condition::any();
+    let mut equal = Condition::all();
+    for ((column, order), value) in sort.columns().into_iter().zip(sort.values(cursor)) {
+        let column_beyond = match (order, after) {
+            (Order::Desc, true) | (Order::Asc, false) => {
+                Expr::expr(column.clone()).lt(value.clone())
+            }
+            _ => Expr::expr(column.clone()).gt(value.clone()),
+        };
+        beyond = beyond.add(equal.clone().add(column_beyond));
+        equal = equal.add(Expr::expr(column).eq(value));
+    }
+    beyond
+}
+
+/// A page of the ranking.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RankingPage {
+    musics: Vec<RankedMusic>,
+    /// The number of musics matching the filters, in every page
+    total: usize,
+    /// The cursor of the next page, `None` on the last page
+    next_cursor: Option<String>,
 }
 
 pub async fn get_musics(
     State(state): State<ApiState>,
     Path(room_id): Path<RoomID>,
+    extract::Query(query): extract::Query<RankingQuery>,
     user: User,
-) -> Result<Json<Vec<Music>>, GetMusicError> {
-    if (Role::User { room_id }) != user.role && user.role != Role::Admin {
+) -> Result<Json<RankingPage>, GetMusicError> {
+    if !has_room_access(&state.db, &user, room_id).await? {
         return Err(GetMusicError::Unauthorized);
     }
+    state.record_activity(room_id, &user);
+
+    if !query.window().is_valid() {
+        return Err(GetMusicError::InvalidWindow);
+    }
+
+    let limit = query.limit.unwrap_or(RankingQuery::DEFAULT_LIMIT);
+    if !(1..=RankingQuery::MAX_LIMIT).contains(&limit) {
+        return Err(GetMusicError::InvalidLimit);
+    }
+    let cursor = match &query.cursor {
+        Some(cursor) => Some(cursor::decode(cursor).ok_or(GetMusicError::InvalidCursor)?),
+        None => None,
+    };
 
     let room = room::Entity::find()
         .filter(room::Column::PublicId.eq(room_id.value()))
@@ -193,19 +1096,265 @@ pub async fn get_musics(
         return Err(GetMusicError::RoomNotFound);
     }
 
+    let is_admin = user.role == Role::Admin;
+    let settings = room_settings(&state.db, room_id).await?;
+    let page = RankingPageQuery {
+        room_id,
+        settings: &settings,
+        query: &query,
+        cursor: cursor.as_ref(),
+        limit,
+        for_dj: is_admin,
+    };
+    let (mut musics, total) = match settings.ranking {
+        RankingAlgorithm::Sum => page.load_by_net_score(&state.db).await?,
+        _ => page.load_scored(&state.db).await?,
+    };
+
+    // One more music is loaded to know if there is a next page
+    let mut next_cursor = None;
+    if musics.len() > limit as usize {
+        musics.truncate(limit as usize);
+        next_cursor = musics
+            .last()
+            .map(|last| cursor::encode(&RankingCursor::new(last)));
+    }
+
+    Ok(Json(RankingPage {
+        musics,
+        total,
+        next_cursor,
+    }))
+}
+
+/// A row of the net score ranking.
+#[derive(FromQueryResult)]
+struct NetScoreRow {
+    id: i64,
+    title: String,
+    artist: String,
+    preview_url: Option<String>,
+    image_hash: Option<String>,
+    votes: i32,
+    likes: u32,
+    dislikes: u32,
+    last_vote: DateTimeUtc,
+}
+
+/// A page of the ranking to load, with a music more than the limit.
+struct RankingPageQuery<'a> {
+    room_id: RoomID,
+    settings: &'a RoomSettings,
+    query: &'a RankingQuery,
+    cursor: Option<&'a RankingCursor>,
+    limit: u32,
+    for_dj: bool,
+}
+
+impl RankingPageQuery<'_> {
+    /// Load the page and the number of musics matching the filters from the net score, all in SQL.
+    async fn load_by_net_score(
+        &self,
+        db: &DatabaseConnection,
+    ) -> Result<(Vec<RankedMusic>, usize), DbErr> {
+        let mut ranking = net_score_ranking(self.room_id, self.query.window(), self.for_dj);
+        // The users only see the top of the ranking, whatever the filters
+        if !self.for_dj {
+            ranking.limit(self.settings.ranking_size.into());
+        }
+
+        let mut filtered = Query::select();
+        filtered
+            .from_subquery(ranking, Alias::new("ranking"))
+            .cond_where(self.query.condition(self.room_id));
+
+        let count = filtered
+            .clone()
+            .expr_as(Expr::asterisk().count(), Alias::new("total"))
+            .take();
+        let total = count_total(db, &count).await?;
+
+        filtered.column(ColumnRef::Asterisk);
+        if let Some(cursor) = self.cursor {
+            filtered.cond_where(beyond_cursor(self.query.sort, cursor, true));
+        }
+        for (column, order) in self.query.sort.columns() {
+            filtered.order_by_expr(column, order);
+        }
+        filtered.limit(u64::from(self.limit) + 1);
+
+        let rows = NetScoreRow::find_by_statement(db.get_database_backend().build(&filtered))
+            .all(db)
+            .await?;
+        let mut requests = room_requests(db, self.room_id).await?;
+        let musics = rows
+            .into_iter()
+            .map(|row| RankedMusic {
+                request: requests.remove(&row.id).unwrap_or_default(),
+                score: row.votes.into(),
+                last_vote: Some(row.last_vote),
+                music: Music {
+                    id: row.id,
+                    title: row.title,
+                    artist: row.artist,
+                    preview_url: row.preview_url,
+                    image_hash: row.image_hash,
+                    votes: row.votes,
+                    likes: row.likes,
+                    dislikes: row.dislikes,
+                },
+            })
+            .collect();
+
+        Ok((musics, total as usize))
+    }
+
+    /// Load the page and the number of musics matching the filters from the whole ranking.
+    ///
+    /// The scores of the other algorithms are computed from every vote, outside of SQL.
+    async fn load_scored(
+        &self,
+        db: &DatabaseConnection,
+    ) -> Result<(Vec<RankedMusic>, usize), DbErr> {
+        let mut ranking = room_ranking(
+            db,
+            self.room_id,
+            self.settings,
+            self.query.window(),
+            self.for_dj,
+        )
+        .await?;
+
+        // The users only see the top of the ranking, whatever the filters
+        if !self.for_dj {
+            ranking.truncate(self.settings.ranking_size as usize);
+        }
+
+        let mut ranking: Vec<(RankingCursor, RankedMusic)> = ranking
+            .into_iter()
+            .filter(|ranked| self.query.matches(ranked))
+            .map(|ranked| (RankingCursor::new(&ranked), ranked))
+            .collect();
+        let sort = self.query.sort;
+        ranking.sort_by(|(a, _), (b, _)| sort.compare(a, b));
+
+        let total = ranking.len();
+        let musics = ranking
+            .into_iter()
+            .filter(|(key, _)| {
+                self.cursor
+                    .is_none_or(|cursor| sort.compare(key, cursor).is_gt())
+            })
+            .map(|(_, ranked)| ranked)
+            .take(self.limit as usize + 1)
+            .collect();
+
+        Ok((musics, total))
+    }
+}
+
+/// Rank the musics of the room with its ranking algorithm.
+///
+/// Only the votes of the window are counted.
+/// The musics without a positive net score and the played musics are only included for the DJ.
+pub(super) async fn room_ranking(
+    db: &DatabaseConnection,
+    room_id: RoomID,
+    settings: &RoomSettings,
+    window: RankingWindow,
+    for_dj: bool,
+) -> Result<Vec<RankedMusic>, DbErr> {
+    let now = Utc::now();
+    let room_filter = votes_filter(room_id, window, now);
+    let statement = ranking_query(room_filter.clone(), for_dj);
+
+    let musics = Music::find_by_statement(db.get_database_backend().build(&statement))
+        .all(db)
+        .await?;
+
+    let mut votes: HashMap<MusicId, Vec<RankedVote>> = HashMap::new();
+    for voted in last_votes(db, room_filter).await? {
+        votes.entry(voted.music_id).or_default().push(RankedVote {
+            value: voted.value,
+            date: voted.vote_date,
+        });
+    }
+
+    let mut requests = room_requests(db, room_id).await?;
+
+    let musics = musics
+        .into_iter()
+        .filter(|music| {
+            let played = requests
+                .get(&music.id)
+                .is_some_and(|request| request.status == RequestStatus::Played);
+            for_dj || !played
+        })
+        .map(|music| {
+            let music_votes = votes.remove(&music.id).unwrap_or_default();
+            let last_vote = music_votes.iter().map(|vote| vote.date).max();
+            ((music, last_vote), music_votes)
+        })
+        .collect();
+
+    let ranking = settings
+        .ranking
+        .rank(musics, now)
+        .into_iter()
+        .map(|((music, last_vote), score)| RankedMusic {
+            request: requests.remove(&music.id).unwrap_or_default(),
+            music,
+            score,
+            last_vote,
+        })
+        .collect();
+
+    Ok(ranking)
+}
+
+/// Build the ranking of the room by net score, like `room_ranking` with the `Sum` algorithm.
+fn net_score_ranking(room_id: RoomID, window: RankingWindow, for_dj: bool) -> SelectStatement {
+    let mut votes_filter = votes_filter(room_id, window, Utc::now());
+    if !for_dj {
+        votes_filter =
+            votes_filter.and(current_vote::Column::MusicId.not_in_subquery(played_ids(room_id)));
+    }
+
+    let mut ranking = ranking_query(votes_filter, for_dj);
+    ranking.expr_as(
+        Expr::col(current_vote::Column::VoteDate).max(),
+        Alias::new("last_vote"),
+    );
+    ranking
+}
+
+/// Only keep the current votes of the room cast during the window.
+fn votes_filter(room_id: RoomID, window: RankingWindow, now: DateTimeUtc) -> SimpleExpr {
+    let room_filter = current_vote::Column::RoomId.eq(room_id.value());
+    match window.start(now) {
+        Some(start) => room_filter.and(current_vote::Column::VoteDate.gte(start)),
+        None => room_filter,
+    }
+}
+
+/// Build the ranking of the musics voted in the rooms matching `room_filter`, by net score.
+///
+/// Only the last vote of each user for a music is counted.
+/// The musics without a positive score are only included if `include_disliked` is set.
+fn ranking_query(room_filter: SimpleExpr, include_disliked: bool) -> SelectStatement {
     let votes = Alias::new("votes");
+    let likes = Alias::new("likes");
+    let dislikes = Alias::new("dislikes");
 
-    let all_votes = vote::Entity::find()
-        .select_only()
-        .column_as(vote::Column::VoteDate.max(), vote::Column::VoteDate)
-        .column(vote::Column::Like)
-        .column(vote::Column::MusicId)
-        .filter(vote::Column::RoomId.eq(room_id.value()))
-        .group_by(vote::Column::UserToken)
-        .group_by(vote::Column::MusicId)
-        .into_query();
-
-    let mut statement = Query::select()
+    let having = match include_disliked {
+        // Every music with at least a like or a dislike, the retracted votes being ignored
+        true => Expr::col(likes.clone())
+            .gt(0)
+            .or(Expr::col(dislikes.clone()).gt(0)),
+        false => Expr::col(votes.clone()).gt(0),
+    };
+
+    Query::select()
         .columns([
             music::Column::Title,
             music::Column::Artist,
@@ -214,50 +1363,65 @@ pub async fn get_musics(
             music::Column::ImageHash,
         ])
         .and_where(music::Column::Id.is_not_null())
-        .expr_as(vote::Column::Like.sum(), votes.clone())
+        .and_where(room_filter)
+        .expr_as(score(), votes.clone())
+        .expr_as(count_votes(VoteValue::Like), likes.clone())
+        .expr_as(count_votes(VoteValue::Dislike), dislikes)
         .group_by_col(music::Column::Id)
-        .from_subquery(all_votes, vote::Entity)
+        .from(current_vote::Entity)
         .join(
             JoinType::LeftJoin,
             music::Entity,
-            Expr::col(vote::Column::MusicId).equals(music::Column::Id),
+            Expr::col(current_vote::Column::MusicId).equals(music::Column::Id),
         )
-        .and_having(Expr::col(votes.clone()).gt(0))
+        .and_having(having)
         .order_by(votes, Order::Desc)
-        .take();
+        .order_by(likes, Order::Desc)
+        .order_by(music::Column::Id, Order::Asc)
+        .take()
+}
 
-    if user.role != Role::Admin {
-        statement.limit(10);
-    }
+/// The net score of the current votes, likes minus dislikes.
+fn score() -> SimpleExpr {
+    Func::coalesce([
+        Func::sum(Expr::col(current_vote::Column::Value)).into(),
+        0.into(),
+    ])
+    .into()
+}
 
-    let backend = state.db.get_database_backend();
+/// Count the current votes having the given value.
+fn count_votes(value: VoteValue) -> SimpleExpr {
+    Func::coalesce([
+        Func::sum(Expr::col(current_vote::Column::Value).eq(value.to_value())).into(),
+        0.into(),
+    ])
+    .into()
+}
 
-    Music::find_by_statement(backend.build(&statement))
-        .all(&state.db)
-        .await
-        .map(Json)
-        .map_err(From::from)
+#[derive(Serialize, Deserialize, Debug)]
+pub struct MusicDetail {
+    #[serde(flatten)]
+    music: Music,
+    #[serde(flatten)]
+    request: Request,
+    /// The current vote of the user for the music
+    my_vote: VoteValue,
+    /// The position of the music in the ranking of the room, starting at 1
+    rank: Option<usize>,
 }
 
 pub async fn get_music_detail(
     State(state): State<ApiState>,
     Path((room_id, music_id)): Path<(RoomID, MusicId)>,
     user: User,
-) -> Result<Json<Music>, GetMusicError> {
-    if (Role::User { room_id }) != user.role && user.role != Role::Admin {
+) -> Result<Json<MusicDetail>, GetMusicError> {
+    if !has_room_access(&state.db, &user, room_id).await? {
         return Err(GetMusicError::Unauthorized);
     }
+    state.record_activity(room_id, &user);
 
-    let all_votes = vote::Entity::find()
-        .select_only()
-        .column_as(vote::Column::VoteDate.max(), "vote_date")
-        .column(vote::Column::Like)
-        .filter(vote::Column::MusicId.eq(music_id))
-        .filter(vote::Column::RoomId.eq(room_id.value()))
-        .left_join(music::Entity)
-        .group_by(vote::Column::UserToken)
-        .into_query();
-
+    // The votes are joined to find the music even without votes in the room
     let statement = Query::select()
         .columns([
             music::Column::Title,
@@ -266,21 +1430,110 @@ pub async fn get_music_detail(
             music::Column::PreviewUrl,
             music::Column::ImageHash,
         ])
-        .expr_as(vote::Column::Like.sum(), Alias::new("votes"))
-        .from_subquery(all_votes, vote::Entity)
+        .expr_as(score(), Alias::new("votes"))
+        .expr_as(count_votes(VoteValue::Like), Alias::new("likes"))
+        .expr_as(count_votes(VoteValue::Dislike), Alias::new("dislikes"))
         .from(music::Entity)
+        .join(
+            JoinType::LeftJoin,
+            current_vote::Entity,
+            Expr::col(current_vote::Column::MusicId)
+                .equals(music::Column::Id)
+                .and(current_vote::Column::RoomId.eq(room_id.value())),
+        )
         .and_where(music::Column::Id.eq(music_id))
+        .group_by_col(music::Column::Id)
         .take();
 
     let backend = state.db.get_database_backend();
 
     let music = Music::find_by_statement(backend.build(&statement))
         .one(&state.db)
-        .await?;
+        .await?
+        .ok_or(GetMusicError::MusicNotFound)?;
+
+    let request = music_request(&state.db, room_id, music_id).await?;
+
+    let my_vote = current_vote::Entity::find_by_id((room_id.value(), user.uid, music_id))
+        .one(&state.db)
+        .await?
+        .map_or(VoteValue::Neutral, |vote| vote.value);
+
+    let is_admin = user.role == Role::Admin;
+    let settings = room_settings(&state.db, room_id).await?;
+    let rank = match settings.ranking {
+        RankingAlgorithm::Sum => net_score_rank(&state.db, room_id, &music, is_admin).await?,
+        // The other scores are computed from every vote
+        _ => room_ranking(
+            &state.db,
+            room_id,
+            &settings,
+            RankingWindow::default(),
+            is_admin,
+        )
+        .await?
+        .iter()
+        .position(|ranked| ranked.music.id == music_id)
+        .map(|position| position + 1),
+    };
+
+    // The users only see the top of the ranking, so the rank is hidden below it
+    let rank = rank.filter(|&rank| is_admin || rank <= settings.ranking_size as usize);
 
-    match music {
-        Some(music) => Ok(Json(music)),
-        None => Err(GetMusicError::MusicNotFound),
+    Ok(Json(MusicDetail {
+        music,
+        request,
+        my_vote,
+        rank,
+    }))
+}
+
+/// Get the position of the music in the ranking by net score, counting the musics before it.
+async fn net_score_rank(
+    db: &DatabaseConnection,
+    room_id: RoomID,
+    music: &Music,
+    for_dj: bool,
+) -> Result<Option<usize>, DbErr> {
+    let ranking = net_score_ranking(room_id, RankingWindow::default(), for_dj);
+    let mut count = Query::select();
+    count
+        .from_subquery(ranking, Alias::new("ranking"))
+        .expr_as(Expr::asterisk().count(), Alias::new("total"));
+
+    let ranked = count
+        .clone()
+        .and_where(Expr::col(music::Column::Id).eq(music.id))
+        .take();
+    if count_total(db, &ranked).await? == 0 {
+        return Ok(None);
+    }
+
+    // The votes of the music count every vote, like the ranking
+    let cursor = RankingCursor {
+        score: music.votes.into(),
+        votes: music.votes,
+        likes: music.likes,
+        last_vote: None,
+        title: music.title.clone(),
+        music_id: music.id,
+    };
+    let before = count
+        .cond_where(beyond_cursor(RankingSort::Score, &cursor, false))
+        .take();
+    let rank = count_total(db, &before).await? as usize + 1;
+
+    Ok(Some(rank))
+}
+
+/// Run a query counting rows as `total`.
+async fn count_total(db: &DatabaseConnection, statement: &SelectStatement) -> Result<u32, DbErr> {
+    match db
+        .query_one(db.get_database_backend().build(statement))
+        .await?
+    {
+        Some(row) => row.try_get("", "total"),
+        None => Ok(0),
     }
 }
 
@@ -288,7 +1541,7 @@ pub async fn get_music_detail(
 pub struct VotedMusic {
     music_id: i64,
     vote_date: DateTimeUtc,
-    like: bool,
+    value: VoteValue,
 }
 
 #[api_macro::error(internal_error, unauthorized)]
@@ -299,18 +1552,40 @@ pub async fn get_voted_musics(
     Path(room_id): Path<RoomID>,
     user: User,
 ) -> Result<Json<Vec<VotedMusic>>, GetVotedMusicError> {
-    if (Role::User { room_id }) != user.role && user.role != Role::Admin {
+    if !has_room_access(&state.db, &user, room_id).await? {
         return Err(GetVotedMusicError::Unauthorized);
     }
+    state.record_activity(room_id, &user);
+
+    let mut user_votes = user_votes(&state.db, room_id, user.uid).await?;
+    user_votes.retain(|voted| voted.value != VoteValue::Neutral);
+
+    Ok(Json(user_votes))
+}
+
+/// Get the current votes matching `filter`, including the retracted ones.
+async fn last_votes(db: &DatabaseConnection, filter: SimpleExpr) -> Result<Vec<VotedMusic>, DbErr> {
+    current_vote::Entity::find()
+        .select_only()
+        .column(current_vote::Column::MusicId)
+        .column(current_vote::Column::VoteDate)
+        .column(current_vote::Column::Value)
+        .filter(filter)
+        .into_model()
+        .all(db)
+        .await
+}
 
-    vote::Entity::find()
-        .column_as(vote::Column::VoteDate.max(), vote::Column::VoteDate)
-        .filter(vote::Column::UserToken.eq(user.uid))
-        .filter(vote::Column::RoomId.eq(room_id.value()))
-        .group_by(vote::Column::MusicId)
+/// Get the current vote of the user for each music of the room, including the retracted ones.
+async fn user_votes(
+    db: &impl ConnectionTrait,
+    room_id: RoomID,
+    user_token: Uuid,
+) -> Result<Vec<VotedMusic>, DbErr> {
+    current_vote::Entity::find()
+        .filter(current_vote::Column::UserToken.eq(user_token))
+        .filter(current_vote::Column::RoomId.eq(room_id.value()))
         .into_model()
-        .all(&state.db)
+        .all(db)
         .await
-        .map(Json)
-        .map_err(From::from)
 }
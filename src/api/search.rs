@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 use axum::{
     extract::{Path, Query, State},
     http::StatusCode,
@@ -7,14 +9,16 @@ use deezer_rs::track::Track;
 use serde::{Deserialize, Serialize};
 
 use entity::{music, room};
-use sea_orm::{prelude::*, IntoActiveModel};
+use sea_orm::{prelude::*, IntoActiveModel, QuerySelect};
 
 use crate::utils::{
     jwt::{Role, User},
     room_id::RoomID,
 };
 
-use super::state::ApiState;
+use super::{
+    blocklist::room_blocklist, event::has_room_access, settings::room_settings, state::ApiState,
+};
 #[derive(Serialize, Deserialize)]
 pub struct SearchRequest {
     pub query: String,
@@ -27,6 +31,7 @@ pub struct SearchMusic {
     pub artist: String,
     pub preview_url: Option<String>,
     pub image_hash: Option<String>,
+    pub explicit: bool,
 }
 
 impl From<Track> for SearchMusic {
@@ -37,6 +42,7 @@ impl From<Track> for SearchMusic {
             artist: music.artist.name,
             preview_url: Some(music.preview),
             image_hash: music.md5_image,
+            explicit: music.explicit_lyrics,
         }
     }
 }
@@ -49,10 +55,20 @@ impl From<SearchMusic> for music::Model {
             artist: music.artist,
             preview_url: music.preview_url,
             image_hash: music.image_hash,
+            explicit: Some(music.explicit),
         }
     }
 }
 
+/// Why a music could not be loaded.
+#[derive(thiserror::Error, displaydoc::Display, Debug)]
+pub enum MusicError {
+    /// Database error: {0}
+    Database(#[from] DbErr),
+    /// Deezer is unavailable: {0}
+    Deezer(#[from] deezer_rs::Error),
+}
+
 #[api_macro::error(internal_error, unauthorized)]
 pub enum SearchError {
     /// Room not found
@@ -61,6 +77,9 @@ pub enum SearchError {
     /// Search failed
     #[status(StatusCode::INTERNAL_SERVER_ERROR)]
     SearchFailed(#[from] deezer_rs::Error),
+    /// Search is closed in this room
+    #[status(StatusCode::FORBIDDEN)]
+    SearchClosed,
 }
 
 pub async fn search(
@@ -69,9 +88,10 @@ pub async fn search(
     Query(request): Query<SearchRequest>,
     user: User,
 ) -> Result<Json<Vec<SearchMusic>>, SearchError> {
-    if (Role::User { room_id }) != user.role && user.role != Role::Admin {
+    if !has_room_access(&state.db, &user, room_id).await? {
         return Err(SearchError::Unauthorized);
     }
+    state.record_activity(room_id, &user);
 
     room::Entity::find()
         .filter(room::Column::PublicId.eq(room_id.value()))
@@ -79,42 +99,101 @@ pub async fn search(
         .await?
         .ok_or(SearchError::RoomNotFound)?;
 
+    let settings = room_settings(&state.db, room_id).await?;
+    if user.role != Role::Admin && !settings.search_open {
+        return Err(SearchError::SearchClosed);
+    }
+
+    state.deezer_throttle.acquire().await;
     let response = state
         .deezer_client
         .search()
         .get_tracks(&request.query)
         .await?;
 
-    let musics = response.data.into_iter().map(SearchMusic::from).collect();
+    let blocklist = room_blocklist(&state.db, room_id).await?;
+    let musics = response
+        .data
+        .into_iter()
+        .map(SearchMusic::from)
+        .filter(|music| settings.allow_explicit || !music.explicit)
+        .filter(|music| !blocklist.blocks(music.id, &music.title, &music.artist))
+        .collect();
 
     Ok(Json(musics))
 }
 
+/// Get the music from the database, or from Deezer if it is not stored yet.
+///
+/// The musics stored before the explicit flag was saved are updated,
+/// or used as they are if Deezer is unavailable.
 pub async fn get_music_or_store_music(
     state: &ApiState,
     music_id: i64,
-) -> Result<music::Model, DbErr> {
+) -> Result<music::Model, MusicError> {
     let music = music::Entity::find()
         .filter(music::Column::Id.eq(music_id))
         .one(&state.db)
         .await?;
 
     match music {
-        Some(music) => Ok(music),
+        Some(music) if music.explicit.is_some() => Ok(music),
+        Some(music) => match fetch_music(state, music_id).await {
+            Ok(fetched) => Ok(fetched
+                .into_active_model()
+                .reset_all()
+                .update(&state.db)
+                .await?),
+            Err(e) => {
+                log::warn!("Using music {} without its explicit flag: {}", music_id, e);
+                Ok(music)
+            }
+        },
         None => {
-            let music = state
-                .deezer_client
-                .track()
-                .get(&music_id.to_string())
-                .await
-                .map(SearchMusic::from)
-                .map(music::Model::from)
-                .map_err(|e| {
-                    log::error!("Failed to get music: {}", e);
-                    DbErr::Custom(e.to_string())
-                })?;
-            let music = music.into_active_model();
-            music.insert(&state.db).await
+            let music = fetch_music(state, music_id).await?.into_active_model();
+            Ok(music.insert(&state.db).await?)
         }
     }
 }
+
+async fn fetch_music(state: &ApiState, music_id: i64) -> Result<music::Model, deezer_rs::Error> {
+    state.deezer_throttle.acquire().await;
+    state
+        .deezer_client
+        .track()
+        .get(&music_id.to_string())
+        .await
+        .map(SearchMusic::from)
+        .map(music::Model::from)
+        .map_err(|e| {
+            log::error!("Failed to get music: {}", e);
+            e
+        })
+}
+
+/// Fetch the explicit flag of the musics stored before it was saved.
+pub(super) async fn backfill_explicit(state: &ApiState) -> Result<(), DbErr> {
+    let music_ids: Vec<i64> = music::Entity::find()
+        .select_only()
+        .column(music::Column::Id)
+        .filter(music::Column::Explicit.is_null())
+        .into_tuple()
+        .all(&state.db)
+        .await?;
+
+    if music_ids.is_empty() {
+        return Ok(());
+    }
+    log::info!("Fetching the explicit flag of {} musics", music_ids.len());
+
+    for music_id in music_ids {
+        // Leave most of the Deezer rate limit to the users
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        if let Err(e) = get_music_or_store_music(state, music_id).await {
+            log::warn!("Failed to backfill music {}: {}", music_id, e);
+        }
+    }
+
+    Ok(())
+}
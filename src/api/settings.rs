@@ -0,0 +1,181 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+
+use entity::{room_settings::RankingAlgorithm, *};
+use sea_orm::{prelude::*, sea_query::OnConflict, ActiveValue::Set};
+
+use crate::utils::{
+    jwt::{Role, User},
+    room_id::RoomID,
+};
+
+use super::{event::has_room_access, state::ApiState};
+
+/// The voting rules of a room.
+///
+/// The settings are replaced as a whole when updating them, every field being required
+/// but the ones added later, which default for the clients not sending them yet.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoomSettings {
+    /// Users can vote against a music
+    pub allow_dislikes: bool,
+    /// Maximum number of musics liked at the same time by a user (no limit if `None`)
+    pub max_upvotes: Option<u32>,
+    /// Minimum time between two votes of a user, in seconds
+    pub vote_cooldown: u32,
+    /// Number of musics of the ranking visible by the users
+    pub ranking_size: u32,
+    /// Users can search musics to vote for
+    pub search_open: bool,
+    /// The way the musics are ranked
+    #[serde(default)]
+    pub ranking: RankingAlgorithm,
+    /// Users can vote for musics with explicit lyrics
+    #[serde(default = "RoomSettings::default_allow_explicit")]
+    pub allow_explicit: bool,
+}
+
+impl Default for RoomSettings {
+    fn default() -> Self {
+        Self {
+            allow_dislikes: true,
+            max_upvotes: None,
+            vote_cooldown: 0,
+            ranking_size: 10,
+            search_open: true,
+            ranking: RankingAlgorithm::default(),
+            allow_explicit: Self::default_allow_explicit(),
+        }
+    }
+}
+
+impl From<room_settings::Model> for RoomSettings {
+    fn from(model: room_settings::Model) -> Self {
+        Self {
+            allow_dislikes: model.allow_dislikes,
+            max_upvotes: model.max_upvotes,
+            vote_cooldown: model.vote_cooldown,
+            ranking_size: model.ranking_size,
+            search_open: model.search_open,
+            ranking: model.ranking,
+            allow_explicit: model.allow_explicit,
+        }
+    }
+}
+
+impl RoomSettings {
+    const MAX_VOTE_COOLDOWN: u32 = 60 * 60;
+    const MAX_RANKING_SIZE: u32 = 100;
+
+    fn default_allow_explicit() -> bool {
+        true
+    }
+
+    fn is_valid(&self) -> bool {
+        self.max_upvotes != Some(0)
+            && self.vote_cooldown <= Self::MAX_VOTE_COOLDOWN
+            && (1..=Self::MAX_RANKING_SIZE).contains(&self.ranking_size)
+    }
+
+    fn to_active_model(&self, room_id: RoomID) -> room_settings::ActiveModel {
+        room_settings::ActiveModel {
+            room_id: Set(room_id.value()),
+            allow_dislikes: Set(self.allow_dislikes),
+            max_upvotes: Set(self.max_upvotes),
+            vote_cooldown: Set(self.vote_cooldown),
+            ranking_size: Set(self.ranking_size),
+            search_open: Set(self.search_open),
+            ranking: Set(self.ranking),
+            allow_explicit: Set(self.allow_explicit),
+        }
+    }
+}
+
+/// Get the settings of the room, or the default settings if the room has none.
+pub async fn room_settings(
+    db: &impl ConnectionTrait,
+    room_id: RoomID,
+) -> Result<RoomSettings, DbErr> {
+    let settings = room_settings::Entity::find_by_id(room_id.value())
+        .one(db)
+        .await?
+        .map(RoomSettings::from)
+        .unwrap_or_default();
+    Ok(settings)
+}
+
+#[api_macro::error(internal_error, unauthorized)]
+pub enum SettingsError {
+    /// Room not found
+    #[status(StatusCode::NOT_FOUND)]
+    RoomNotFound,
+    /// Invalid settings
+    #[status(StatusCode::BAD_REQUEST)]
+    InvalidSettings,
+}
+
+pub async fn get_settings(
+    State(state): State<ApiState>,
+    Path(room_id): Path<RoomID>,
+    user: User,
+) -> Result<Json<RoomSettings>, SettingsError> {
+    if !has_room_access(&state.db, &user, room_id).await? {
+        return Err(SettingsError::Unauthorized);
+    }
+
+    room::Entity::find()
+        .filter(room::Column::PublicId.eq(room_id.value()))
+        .one(&state.db)
+        .await?
+        .ok_or(SettingsError::RoomNotFound)?;
+
+    room_settings(&state.db, room_id)
+        .await
+        .map(Json)
+        .map_err(From::from)
+}
+
+/// Replace the settings of the room.
+pub async fn update_settings(
+    State(state): State<ApiState>,
+    Path(room_id): Path<RoomID>,
+    user: User,
+    Json(settings): Json<RoomSettings>,
+) -> Result<Json<RoomSettings>, SettingsError> {
+    if user.role != Role::Admin {
+        return Err(SettingsError::Unauthorized);
+    }
+
+    if !settings.is_valid() {
+        return Err(SettingsError::InvalidSettings);
+    }
+
+    room::Entity::find()
+        .filter(room::Column::PublicId.eq(room_id.value()))
+        .one(&state.db)
+        .await?
+        .ok_or(SettingsError::RoomNotFound)?;
+
+    room_settings::Entity::insert(settings.to_active_model(room_id))
+        .on_conflict(
+            OnConflict::column(room_settings::Column::RoomId)
+                .update_columns([
+                    room_settings::Column::AllowDislikes,
+                    room_settings::Column::MaxUpvotes,
+                    room_settings::Column::VoteCooldown,
+                    room_settings::Column::RankingSize,
+                    room_settings::Column::SearchOpen,
+                    room_settings::Column::Ranking,
+                    room_settings::Column::AllowExplicit,
+                ])
+                .to_owned(),
+        )
+        .exec(&state.db)
+        .await?;
+
+    Ok(Json(settings))
+}
@@ -0,0 +1,220 @@
+use axum::{
+    extract::{Host, Path, Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Redirect, Response},
+};
+
+use serde::Deserialize;
+
+use entity::{event, room};
+use sea_orm::prelude::*;
+
+use crate::utils::{
+    flyer::{Flyer, FlyerTemplate},
+    jwt::{Role, User},
+    pdf::PageSize,
+    qr::{ErrorCorrection, QrImage, QrOptions},
+    room_id::RoomID,
+};
+
+use super::state::ApiState;
+
+/// Build the public join link of the room.
+///
+/// The link uses the `PUBLIC_URL` if set, or the host of the request.
+pub fn join_url(state: &ApiState, host: &str, room_id: RoomID) -> String {
+    match &state.public_url {
+        Some(public_url) => format!("{public_url}/j/{room_id}"),
+        None => {
+            let scheme = if cfg!(feature = "https") {
+                "https"
+            } else {
+                "http"
+            };
+            format!("{scheme}://{host}/j/{room_id}")
+        }
+    }
+}
+
+/// Redirect the short join link to the room page.
+pub async fn join_redirect(Path(room_id): Path<RoomID>) -> Redirect {
+    Redirect::temporary(&format!("/r/{room_id}"))
+}
+
+#[api_macro::error(internal_error)]
+pub enum QrCodeError {
+    /// Room not found
+    #[status(StatusCode::NOT_FOUND)]
+    RoomNotFound,
+    /// Invalid QR code options
+    #[status(StatusCode::BAD_REQUEST)]
+    InvalidOptions,
+    /// The QR code could not be generated
+    #[status(StatusCode::INTERNAL_SERVER_ERROR)]
+    GenerationFailed,
+}
+
+pub async fn qr_svg(
+    State(state): State<ApiState>,
+    Path(room_id): Path<RoomID>,
+    Host(host): Host,
+    Query(options): Query<QrOptions>,
+) -> Result<Response, QrCodeError> {
+    let image = room_qr_code(&state, &host, room_id, options).await?;
+
+    Ok(([(header::CONTENT_TYPE, "image/svg+xml")], image.to_svg()).into_response())
+}
+
+pub async fn qr_png(
+    State(state): State<ApiState>,
+    Path(room_id): Path<RoomID>,
+    Host(host): Host,
+    Query(options): Query<QrOptions>,
+) -> Result<Response, QrCodeError> {
+    let image = room_qr_code(&state, &host, room_id, options).await?;
+
+    // The rasterization is too slow for the async executor
+    let png = tokio::task::spawn_blocking(move || image.to_png())
+        .await
+        .map_err(|err| {
+            log::error!("Failed to render QR code: {}", err);
+            QrCodeError::GenerationFailed
+        })?
+        .map_err(|err| {
+            log::error!("Failed to encode QR code: {}", err);
+            QrCodeError::GenerationFailed
+        })?;
+
+    Ok(([(header::CONTENT_TYPE, "image/png")], png).into_response())
+}
+
+/// Generate the QR code of the room join link.
+async fn room_qr_code(
+    state: &ApiState,
+    host: &str,
+    room_id: RoomID,
+    options: QrOptions,
+) -> Result<QrImage, QrCodeError> {
+    if options.size > QrOptions::MAX_SIZE || options.margin > QrOptions::MAX_MARGIN {
+        return Err(QrCodeError::InvalidOptions);
+    }
+
+    room::Entity::find()
+        .filter(room::Column::PublicId.eq(room_id.value()))
+        .one(&state.db)
+        .await?
+        .ok_or(QrCodeError::RoomNotFound)?;
+
+    QrImage::new(&join_url(state, host, room_id), options).map_err(|err| {
+        log::error!("Failed to generate QR code: {}", err);
+        QrCodeError::GenerationFailed
+    })
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FlyerOptions {
+    #[serde(default)]
+    template: FlyerTemplate,
+    #[serde(default = "FlyerOptions::default_page")]
+    page: PageSize,
+    title: Option<String>,
+    blurb: Option<String>,
+}
+
+impl FlyerOptions {
+    const MAX_TITLE_LENGTH: usize = 60;
+    const MAX_BLURB_LENGTH: usize = 200;
+
+    fn default_page() -> PageSize {
+        PageSize::A4
+    }
+}
+
+#[api_macro::error(internal_error, unauthorized)]
+pub enum FlyerError {
+    /// Room not found
+    #[status(StatusCode::NOT_FOUND)]
+    RoomNotFound,
+    /// The title or the blurb is too long
+    #[status(StatusCode::BAD_REQUEST)]
+    TextTooLong,
+    /// The QR code could not be generated
+    #[status(StatusCode::INTERNAL_SERVER_ERROR)]
+    GenerationFailed,
+}
+
+/// Generate a printable PDF flyer of the room.
+pub async fn flyer(
+    State(state): State<ApiState>,
+    Path(room_id): Path<RoomID>,
+    Host(host): Host,
+    Query(options): Query<FlyerOptions>,
+    user: User,
+) -> Result<Response, FlyerError> {
+    if user.role != Role::Admin {
+        return Err(FlyerError::Unauthorized);
+    }
+
+    let title_length = options
+        .title
+        .as_ref()
+        .map_or(0, |title| title.chars().count());
+    let blurb_length = options
+        .blurb
+        .as_ref()
+        .map_or(0, |blurb| blurb.chars().count());
+    if title_length > FlyerOptions::MAX_TITLE_LENGTH
+        || blurb_length > FlyerOptions::MAX_BLURB_LENGTH
+    {
+        return Err(FlyerError::TextTooLong);
+    }
+
+    let (room, event) = room::Entity::find()
+        .filter(room::Column::PublicId.eq(room_id.value()))
+        .find_also_related(event::Entity)
+        .one(&state.db)
+        .await?
+        .ok_or(FlyerError::RoomNotFound)?;
+
+    let url = join_url(&state, &host, RoomID::new(room.public_id));
+    let qr_options = QrOptions {
+        margin: 0,
+        // The cards might get damaged on the tables
+        ec: ErrorCorrection::Q,
+        ..Default::default()
+    };
+    let qr_code = QrImage::new(&url, qr_options).map_err(|err| {
+        log::error!("Failed to generate QR code: {}", err);
+        FlyerError::GenerationFailed
+    })?;
+
+    let title = options
+        .title
+        .or_else(|| event.map(|event| event.name))
+        .unwrap_or_else(|| "DJ-store".to_string());
+    let subtitle = format!("Room {room_id}");
+    let blurb = options
+        .blurb
+        .unwrap_or_else(|| "Scan the QR code and vote for the next song!".to_string());
+
+    let flyer = Flyer {
+        title: &title,
+        subtitle: &subtitle,
+        blurb: &blurb,
+        url: &url,
+        qr_code: &qr_code,
+    };
+    let pdf = flyer.render(options.template, options.page).to_bytes();
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/pdf".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("inline; filename=\"flyer-{room_id}.pdf\""),
+            ),
+        ],
+        pdf,
+    )
+        .into_response())
+}
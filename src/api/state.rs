@@ -1,38 +1,128 @@
 use std::{
-    collections::BTreeMap,
+    collections::{BTreeMap, HashMap, HashSet, VecDeque},
+    net::IpAddr,
     ops::{Deref, DerefMut},
-    sync::{Arc, RwLock},
+    sync::{Arc, Mutex, RwLock},
+    time::{Duration, Instant},
 };
 
 use argon2::PasswordHash;
+use axum::extract::FromRef;
 use deezer_rs::Deezer;
 use sea_orm::DatabaseConnection;
 use tokio::sync::broadcast::{Receiver, Sender};
+use uuid::Uuid;
 
-use crate::utils::room_id::RoomID;
+use crate::utils::{
+    abuse::AbuseDetector,
+    client_ip::TrustedProxies,
+    jwt::{Role, User},
+    rate_limit::RateLimiter,
+    room_id::RoomID,
+    throttle::Throttle,
+};
 
-use super::websocket::VoteEvent;
+use super::{
+    websocket::{AbuseAlert, AudienceEvent, PresenceEvent, RoomEvent, VoteEvent},
+    MusicId,
+};
 
 #[derive(Clone)]
 pub struct ApiState {
     pub db: DatabaseConnection,
     pub deezer_client: Deezer,
+    /// Every call to Deezer waits its turn, to stay under its rate limit
+    pub deezer_throttle: Throttle,
     pub rooms_channels: RoomChannels,
+    /// The events sent to the users of the rooms
+    pub audience_channels: RoomChannels<AudienceEvent>,
+    pub presence: Presence,
+    pub vote_guard: VoteGuard,
     // TODO: Use global static variable instead of Arc again is better ?
     // the admin_info is only inizialized once, and cannot be changed
     pub admin_info: Arc<AdminInfo>,
+    /// The public URL of the app, used to build the join links.
+    pub public_url: Option<Arc<str>>,
+    pub trusted_proxies: TrustedProxies,
+}
+
+impl FromRef<ApiState> for TrustedProxies {
+    fn from_ref(state: &ApiState) -> Self {
+        state.trusted_proxies.clone()
+    }
 }
 
 impl ApiState {
-    pub fn new(db: DatabaseConnection, admin_username: String, password_hash: String) -> Self {
+    pub fn new(
+        db: DatabaseConnection,
+        admin_username: String,
+        password_hash: String,
+        public_url: Option<String>,
+        trusted_proxies: TrustedProxies,
+    ) -> Self {
         let client = Deezer::new();
         let admin_info = AdminInfo::new(admin_username, password_hash);
+        let public_url = public_url.map(|url| url.trim_end_matches('/').into());
 
         Self {
             db,
             deezer_client: client,
+            // Deezer API rate limit is 50 requests per 5 seconds
+            deezer_throttle: Throttle::new(40, Duration::from_secs(5)),
             rooms_channels: RoomChannels::new(),
+            audience_channels: RoomChannels::new(),
+            presence: Presence::new(),
+            vote_guard: VoteGuard::new(),
             admin_info: Arc::new(admin_info),
+            public_url,
+            trusted_proxies,
+        }
+    }
+
+    /// Mark the user as active in the room,
+    /// and send the new presence to the DJ if the user was not active yet.
+    pub fn record_activity(&self, room_id: RoomID, user: &User) {
+        if user.role == Role::Admin {
+            return;
+        }
+        if let Some(active_users) = self.presence.touch(room_id, user.uid) {
+            let event = RoomEvent::Presence(PresenceEvent { active_users });
+            self.rooms_channels.send(room_id, event);
+        }
+    }
+
+    /// Fetch the missing explicit flags of the stored musics.
+    pub async fn backfill_explicit(self) {
+        if let Err(e) = super::search::backfill_explicit(&self).await {
+            log::error!("Failed to backfill the explicit flags: {}", e);
+        }
+    }
+
+    /// Close the polls left open at their deadline.
+    pub async fn schedule_open_polls(self) {
+        if let Err(e) = super::poll::schedule_open_polls(&self).await {
+            log::error!("Failed to schedule the open polls: {}", e);
+        }
+    }
+
+    /// Periodically remove the inactive users and send the new presence to the DJ.
+    pub async fn sweep_presence(self) {
+        let mut interval = tokio::time::interval(Duration::from_secs(30));
+        loop {
+            interval.tick().await;
+            for (room_id, active_users) in self.presence.sweep() {
+                let event = RoomEvent::Presence(PresenceEvent { active_users });
+                self.rooms_channels.send(room_id, event);
+            }
+        }
+    }
+
+    /// Periodically forget the old votes of the rate limits and the abuse detector.
+    pub async fn sweep_vote_guard(self) {
+        let mut interval = tokio::time::interval(Duration::from_secs(60));
+        loop {
+            interval.tick().await;
+            self.vote_guard.sweep();
         }
     }
 }
@@ -50,34 +140,49 @@ impl AdminInfo {
     }
 }
 
-#[derive(Clone)]
-pub struct RoomChannels {
-    channels: Arc<RwLock<BTreeMap<RoomID, Sender<VoteEvent>>>>,
+/// A broadcast channel per room, opened while someone listens to it.
+pub struct RoomChannels<E = RoomEvent> {
+    channels: Arc<RwLock<BTreeMap<RoomID, Sender<E>>>>,
+}
+
+// Derived `Clone` would require `E: Clone`
+impl<E> Clone for RoomChannels<E> {
+    fn clone(&self) -> Self {
+        Self {
+            channels: self.channels.clone(),
+        }
+    }
 }
 
 impl RoomChannels {
+    pub fn send_vote(&self, room_id: RoomID, vote: VoteEvent) {
+        self.send(room_id, RoomEvent::Vote(vote));
+    }
+}
+
+impl<E: Clone> RoomChannels<E> {
     pub fn new() -> Self {
         Self {
             channels: Arc::new(RwLock::new(BTreeMap::new())),
         }
     }
 
-    pub fn send_vote(&self, room_id: RoomID, vote: VoteEvent) {
+    pub fn send(&self, room_id: RoomID, event: E) {
         let channels = match self.channels.read() {
             Ok(channels) => channels,
             Err(_) => {
-                log::error!("Failed to send vote to room {}: Poisoned lock", room_id);
+                log::error!("Failed to send event to room {}: Poisoned lock", room_id);
                 return;
             }
         };
         if let Some(channel) = channels.get(&room_id) {
-            if let Err(err) = channel.send(vote) {
-                log::warn!("Failed to send vote to room {}: {}", room_id, err);
+            if let Err(err) = channel.send(event) {
+                log::warn!("Failed to send event to room {}: {}", room_id, err);
             }
         }
     }
 
-    pub fn subscribe(&self, room_id: RoomID) -> Option<ReceiverGuard> {
+    pub fn subscribe(&self, room_id: RoomID) -> Option<ReceiverGuard<E>> {
         let mut channels = self
             .channels
             .write()
@@ -100,27 +205,27 @@ impl RoomChannels {
     }
 }
 
-pub struct ReceiverGuard {
+pub struct ReceiverGuard<E = RoomEvent> {
     room_id: RoomID,
-    receiver: Option<Receiver<VoteEvent>>,
-    guard: RoomChannels,
+    receiver: Option<Receiver<E>>,
+    guard: RoomChannels<E>,
 }
 
-impl Deref for ReceiverGuard {
-    type Target = Receiver<VoteEvent>;
+impl<E> Deref for ReceiverGuard<E> {
+    type Target = Receiver<E>;
 
     fn deref(&self) -> &Self::Target {
         self.receiver.as_ref().unwrap()
     }
 }
 
-impl DerefMut for ReceiverGuard {
+impl<E> DerefMut for ReceiverGuard<E> {
     fn deref_mut(&mut self) -> &mut Self::Target {
         self.receiver.as_mut().unwrap()
     }
 }
 
-impl Drop for ReceiverGuard {
+impl<E> Drop for ReceiverGuard<E> {
     fn drop(&mut self) {
         match self.guard.channels.write() {
             Ok(mut channels) => {
@@ -143,3 +248,239 @@ impl Drop for ReceiverGuard {
         }
     }
 }
+
+/// How long a user is considered active after its last activity.
+const PRESENCE_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+/// The users recently active in each room.
+///
+/// A user is active if they made a request in the room (or sent a heartbeat)
+/// during the last [`PRESENCE_TIMEOUT`].
+#[derive(Clone)]
+pub struct Presence {
+    rooms: Arc<RwLock<BTreeMap<RoomID, HashMap<Uuid, Instant>>>>,
+}
+
+impl Presence {
+    pub fn new() -> Self {
+        Self {
+            rooms: Arc::new(RwLock::new(BTreeMap::new())),
+        }
+    }
+
+    /// Record an activity of the user in the room.
+    ///
+    /// Returns the number of active users if the user was not active before.
+    pub fn touch(&self, room_id: RoomID, uid: Uuid) -> Option<usize> {
+        let mut rooms = match self.rooms.write() {
+            Ok(rooms) => rooms,
+            Err(_) => {
+                log::error!(
+                    "Failed to update presence of room {}: Poisoned lock",
+                    room_id
+                );
+                return None;
+            }
+        };
+        let now = Instant::now();
+        let users = rooms.entry(room_id).or_default();
+        match users.insert(uid, now) {
+            Some(last_seen) if now.duration_since(last_seen) < PRESENCE_TIMEOUT => None,
+            _ => Some(count_active(users, now)),
+        }
+    }
+
+    /// Get the number of active users in the room.
+    pub fn active_users(&self, room_id: RoomID) -> usize {
+        self.active_users_in([room_id])
+    }
+
+    /// Get the number of distinct active users in the rooms.
+    pub fn active_users_in(&self, rooms_id: impl IntoIterator<Item = RoomID>) -> usize {
+        let rooms = match self.rooms.read() {
+            Ok(rooms) => rooms,
+            Err(_) => {
+                log::error!("Failed to read presence: Poisoned lock");
+                return 0;
+            }
+        };
+        let now = Instant::now();
+        let mut active = HashSet::new();
+        for room_id in rooms_id {
+            if let Some(users) = rooms.get(&room_id) {
+                active.extend(
+                    users
+                        .iter()
+                        .filter(|(_, last_seen)| now.duration_since(**last_seen) < PRESENCE_TIMEOUT)
+                        .map(|(uid, _)| *uid),
+                );
+            }
+        }
+        active.len()
+    }
+
+    /// Remove the inactive users.
+    ///
+    /// Returns the rooms where users were removed, with their new number of active users.
+    pub fn sweep(&self) -> Vec<(RoomID, usize)> {
+        let mut rooms = match self.rooms.write() {
+            Ok(rooms) => rooms,
+            Err(_) => {
+                log::error!("Failed to sweep presence: Poisoned lock");
+                return Vec::new();
+            }
+        };
+        let now = Instant::now();
+        let mut changed = Vec::new();
+        for (room_id, users) in rooms.iter_mut() {
+            let before = users.len();
+            users.retain(|_, last_seen| now.duration_since(*last_seen) < PRESENCE_TIMEOUT);
+            if users.len() != before {
+                changed.push((*room_id, users.len()));
+            }
+        }
+        rooms.retain(|_, users| !users.is_empty());
+        changed
+    }
+}
+
+fn count_active(users: &HashMap<Uuid, Instant>, now: Instant) -> usize {
+    users
+        .values()
+        .filter(|last_seen| now.duration_since(**last_seen) < PRESENCE_TIMEOUT)
+        .count()
+}
+
+/// The maximum number of votes of a user per minute.
+const USER_VOTES_PER_MINUTE: usize = 20;
+/// The maximum number of votes from an address per minute,
+/// the guests of a venue may share the same address.
+const ADDRESS_VOTES_PER_MINUTE: usize = 300;
+/// The number of abuse alerts kept for each room.
+const MAX_ROOM_ALERTS: usize = 50;
+/// How long the abuse alerts are kept, in hours.
+const ALERT_RETENTION_HOURS: i64 = 24;
+
+/// The rate limits of the votes and the abuse detection.
+#[derive(Clone)]
+pub struct VoteGuard {
+    inner: Arc<Mutex<VoteGuardState>>,
+}
+
+struct VoteGuardState {
+    users: RateLimiter<Uuid>,
+    addresses: RateLimiter<IpAddr>,
+    detector: AbuseDetector,
+    /// The last alerts of each room, the most recent last
+    alerts: BTreeMap<RoomID, VecDeque<AbuseAlert>>,
+}
+
+impl VoteGuard {
+    pub fn new() -> Self {
+        let state = VoteGuardState {
+            users: RateLimiter::new(USER_VOTES_PER_MINUTE, Duration::from_secs(60)),
+            addresses: RateLimiter::new(ADDRESS_VOTES_PER_MINUTE, Duration::from_secs(60)),
+            detector: AbuseDetector::default(),
+            alerts: BTreeMap::new(),
+        };
+        Self {
+            inner: Arc::new(Mutex::new(state)),
+        }
+    }
+
+    /// Check if the user can vote from the address, counting the vote if they can.
+    pub fn try_vote(&self, uid: Uuid, ip: IpAddr) -> bool {
+        let mut state = match self.inner.lock() {
+            Ok(state) => state,
+            Err(_) => {
+                log::error!("Failed to check the vote rate limit: Poisoned lock");
+                return true;
+            }
+        };
+        let now = Instant::now();
+        // A limited user must not use the votes of the other users of the address
+        if state.users.is_limited(&uid, now) || state.addresses.is_limited(&ip, now) {
+            return false;
+        }
+        state.users.try_acquire(uid, now) && state.addresses.try_acquire(ip, now)
+    }
+
+    /// Record a vote for the abuse detection.
+    ///
+    /// Returns the alert to send to the DJ if the vote starts a burst of fresh users.
+    pub fn record(
+        &self,
+        room_id: RoomID,
+        uid: Uuid,
+        ip: IpAddr,
+        music_id: MusicId,
+    ) -> Option<AbuseAlert> {
+        let mut state = match self.inner.lock() {
+            Ok(state) => state,
+            Err(_) => {
+                log::error!("Failed to record vote of room {}: Poisoned lock", room_id);
+                return None;
+            }
+        };
+        let users = state
+            .detector
+            .record(room_id, uid, ip, music_id, Instant::now())?;
+
+        log::warn!(
+            "{} fresh users from {} voted music {} in room {}",
+            users,
+            ip,
+            music_id,
+            room_id
+        );
+        let alert = AbuseAlert {
+            ip,
+            music_id,
+            users,
+            date: chrono::Utc::now(),
+        };
+        let alerts = state.alerts.entry(room_id).or_default();
+        if alerts.len() >= MAX_ROOM_ALERTS {
+            alerts.pop_front();
+        }
+        alerts.push_back(alert);
+        Some(alert)
+    }
+
+    /// Get the abuse alerts of the room, the most recent first.
+    pub fn alerts(&self, room_id: RoomID) -> Vec<AbuseAlert> {
+        let state = match self.inner.lock() {
+            Ok(state) => state,
+            Err(_) => {
+                log::error!("Failed to read alerts of room {}: Poisoned lock", room_id);
+                return Vec::new();
+            }
+        };
+        state
+            .alerts
+            .get(&room_id)
+            .map(|alerts| alerts.iter().rev().copied().collect())
+            .unwrap_or_default()
+    }
+
+    /// Forget the old votes and alerts.
+    pub fn sweep(&self) {
+        let mut state = match self.inner.lock() {
+            Ok(state) => state,
+            Err(_) => {
+                log::error!("Failed to sweep the vote guard: Poisoned lock");
+                return;
+            }
+        };
+        let now = Instant::now();
+        state.users.sweep(now);
+        state.addresses.sweep(now);
+        state.detector.sweep(now);
+
+        let oldest = chrono::Utc::now() - chrono::Duration::hours(ALERT_RETENTION_HOURS);
+        state.alerts.retain(|_, alerts| {
+            alerts.retain(|alert| alert.date > oldest);
+            !alerts.is_empty()
+        });
+    }
+}
@@ -1,4 +1,4 @@
-use std::time::Duration;
+use std::{collections::HashMap, net::IpAddr, sync::Arc, time::Duration};
 
 use axum::{
     extract::{
@@ -9,22 +9,158 @@ use axum::{
     response::Response,
     Error,
 };
+use chrono::{DateTime, Utc};
 // use deku::{DekuContainerWrite, DekuUpdate, DekuWrite};
-use serde::Serialize;
-use tokio::{select, time::timeout};
+use serde::{Deserialize, Serialize};
+use tokio::{
+    select,
+    time::{interval, timeout, Instant},
+};
+use uuid::Uuid;
+
+use entity::vote::VoteValue;
 
+use crate::utils::energy::EnergyMeter;
 use crate::utils::jwt::{self, Role};
 use crate::utils::room_id::RoomID;
 
 use super::{
+    dedication::Dedication,
+    energy::energy_meter,
+    event::has_room_access,
+    poll::PollResults,
+    room::{room_ranking, RankedMusic, RankingWindow},
+    settings::room_settings,
     state::{ApiState, ReceiverGuard},
     MusicId,
 };
 
+/// Check for a new ranking to send at this interval.
+const RANKING_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+/// Send the ranking at least at this interval, as the votes leave the window.
+const RANKING_REFRESH_INTERVAL: Duration = Duration::from_secs(30);
+/// Check for a new energy meter at this interval, as the signals leave its window.
+const ENERGY_REFRESH_INTERVAL: Duration = Duration::from_secs(30);
+
+/// The events sent to the DJ on the room websocket.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RoomEvent {
+    Vote(VoteEvent),
+    Presence(PresenceEvent),
+    NowPlaying(NowPlayingEvent),
+    Abuse(AbuseAlert),
+    /// A new dedication waiting for the DJ approval, not to be shown as is
+    DedicationPending(Dedication),
+    /// A dedication approved or hidden by the DJ
+    Dedication(Dedication),
+    /// The signals of the crowd of the last minutes
+    Energy(EnergyMeter),
+    /// A poll was opened, voted or closed
+    Poll(PollResults),
+}
+
 #[derive(Debug, Clone, Copy, Serialize)]
 pub struct VoteEvent {
     pub music_id: MusicId,
-    pub like: bool,
+    pub value: VoteValue,
+    /// The vote replaced by this one (`neutral` if the user had not voted yet)
+    pub previous: VoteValue,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct PresenceEvent {
+    pub active_users: usize,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct NowPlayingEvent {
+    pub music_id: Option<MusicId>,
+}
+
+/// A burst of fresh users from the same address voting the same music.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct AbuseAlert {
+    pub ip: IpAddr,
+    pub music_id: MusicId,
+    /// The number of fresh users of the burst
+    pub users: usize,
+    pub date: DateTime<Utc>,
+}
+
+/// The events sent to the users on the audience websocket.
+///
+/// Every socket of the room receives them, but only sends its own user the messages for them.
+#[derive(Debug, Clone)]
+pub enum AudienceEvent {
+    /// Sent to every user
+    NowPlaying(NowPlayingEvent),
+    /// A poll was opened, voted or closed, sent to every user
+    Poll(PollResults),
+    RequestPlaying(RequestPlayingEvent),
+}
+
+/// A music liked by some users was marked as played.
+#[derive(Debug, Clone)]
+pub struct RequestPlayingEvent {
+    pub music_id: MusicId,
+    pub title: String,
+    pub artist: String,
+    /// The users who liked the music, with their number of played requests in the room
+    pub requesters: Arc<HashMap<Uuid, u32>>,
+}
+
+/// The messages sent to a user on the audience websocket.
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum AudienceMessage<'a> {
+    NowPlaying(NowPlayingEvent),
+    Poll(&'a PollResults),
+    /// A music liked by the user is playing
+    RequestPlaying {
+        music_id: MusicId,
+        title: &'a str,
+        artist: &'a str,
+        /// The number of musics liked by the user and played in the room, this one included
+        played_requests: u32,
+    },
+}
+
+impl AudienceEvent {
+    /// The message for the user, if the event concerns them.
+    fn message_for(&self, uid: Uuid) -> Option<AudienceMessage<'_>> {
+        match self {
+            AudienceEvent::NowPlaying(event) => Some(AudienceMessage::NowPlaying(*event)),
+            AudienceEvent::Poll(results) => Some(AudienceMessage::Poll(results)),
+            AudienceEvent::RequestPlaying(event) => {
+                let played_requests = *event.requesters.get(&uid)?;
+                Some(AudienceMessage::RequestPlaying {
+                    music_id: event.music_id,
+                    title: &event.title,
+                    artist: &event.artist,
+                    played_requests,
+                })
+            }
+        }
+    }
+}
+
+/// The messages sent by the DJ on the room websocket, after the auth token.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ClientMessage {
+    /// Receive the ranking of the votes of the window when it changes
+    SubscribeRanking(RankingWindow),
+    UnsubscribeRanking,
+}
+
+/// The ranking of the window subscribed by the DJ.
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename = "ranking")]
+struct RankingFeed {
+    #[serde(flatten)]
+    window: RankingWindow,
+    musics: Vec<RankedMusic>,
 }
 
 pub async fn handle_request(
@@ -37,13 +173,24 @@ pub async fn handle_request(
     // user: User,
     Path(room_id): Path<RoomID>,
 ) -> Result<Response, (StatusCode, &'static str)> {
+    let presence = PresenceEvent {
+        active_users: state.presence.active_users(room_id),
+    };
     match state.rooms_channels.subscribe(room_id) {
-        Some(receiver) => Ok(ws.on_upgrade(move |socket| handle_room_websocket(socket, receiver))),
+        Some(receiver) => Ok(ws.on_upgrade(move |socket| {
+            handle_room_websocket(socket, state, room_id, receiver, presence)
+        })),
         None => Err((StatusCode::NOT_FOUND, "Room not found")),
     }
 }
 
-async fn handle_room_websocket(mut socket: WebSocket, mut room_receiver: ReceiverGuard) {
+async fn handle_room_websocket(
+    mut socket: WebSocket,
+    state: ApiState,
+    room_id: RoomID,
+    mut room_receiver: ReceiverGuard,
+    presence: PresenceEvent,
+) {
     // Check the first message is an admin auth token
     let future = timeout(Duration::from_secs(3), socket.recv());
     match future.await {
@@ -68,27 +215,183 @@ async fn handle_room_websocket(mut socket: WebSocket, mut room_receiver: Receive
         }
     }
 
+    let encoded = serde_json::to_string(&RoomEvent::Presence(presence)).unwrap();
+    if let Err(e) = socket.send(Message::Text(encoded)).await {
+        log::error!("Error sending presence: {}", e);
+        return;
+    }
+
+    let mut ranking_window = None;
+    // The ranking changed since it was last sent
+    let mut ranking_stale = false;
+    let mut ranking_sent = Instant::now();
+    let mut ranking_check = interval(RANKING_CHECK_INTERVAL);
+    // The last energy meter sent, the first one being sent on connection
+    let mut energy_sent = None;
+    let mut energy_check = interval(ENERGY_REFRESH_INTERVAL);
+
     loop {
         select! {
             msg = socket.recv() => {
-                if matches!(msg, Some(Ok(Message::Close(_)) | Err(_)) | None) {
+                match msg {
+                    Some(Ok(Message::Close(_)) | Err(_)) | None => break,
+                    Some(Ok(Message::Text(text))) => match serde_json::from_str(&text) {
+                        Ok(ClientMessage::SubscribeRanking(window)) if window.is_valid() => {
+                            ranking_window = Some(window);
+                            ranking_stale = true;
+                        }
+                        Ok(ClientMessage::UnsubscribeRanking) => ranking_window = None,
+                        _ => log::warn!("Received invalid websocket message: {}", text),
+                    },
+                    msg => log::warn!("Received invalid websocket message: {:?}", msg),
+                }
+            }
+            Ok(event) = room_receiver.recv() => {
+                match event {
+                    RoomEvent::Vote(_) => ranking_stale = true,
+                    RoomEvent::Energy(meter) => energy_sent = Some(meter),
+                    _ => {}
+                }
+                let encoded = serde_json::to_string(&event).unwrap();
+                if let Err(e) = socket.send(Message::Text(encoded)).await {
+                    log::error!("Error sending room event: {}", e);
                     break;
-                } else {
-                    log::warn!("Received invalid websocket message: {:?}", msg);
                 }
             }
-            Ok(vote) = room_receiver.recv() => {
-                let encoded = serde_json::to_string(&vote).unwrap();
+            _ = ranking_check.tick() => {
+                let Some(window) = ranking_window else {
+                    continue;
+                };
+                if !ranking_stale && ranking_sent.elapsed() < RANKING_REFRESH_INTERVAL {
+                    continue;
+                }
+
+                let ranking = match room_settings(&state.db, room_id).await {
+                    Ok(settings) => room_ranking(&state.db, room_id, &settings, window, true).await,
+                    Err(e) => Err(e),
+                };
+                let musics = match ranking {
+                    Ok(musics) => musics,
+                    Err(e) => {
+                        log::error!("Error computing the ranking of room {}: {}", room_id, e);
+                        continue;
+                    }
+                };
+
+                let encoded = serde_json::to_string(&RankingFeed { window, musics }).unwrap();
                 if let Err(e) = socket.send(Message::Text(encoded)).await {
-                    log::error!("Error sending vote: {}", e);
+                    log::error!("Error sending ranking: {}", e);
                     break;
                 }
+                ranking_stale = false;
+                ranking_sent = Instant::now();
+            }
+            _ = energy_check.tick() => {
+                let meter = match energy_meter(&state.db, room_id).await {
+                    Ok(meter) => meter,
+                    Err(e) => {
+                        log::error!("Error computing the energy of room {}: {}", room_id, e);
+                        continue;
+                    }
+                };
+                if energy_sent == Some(meter) {
+                    continue;
+                }
+
+                let encoded = serde_json::to_string(&RoomEvent::Energy(meter)).unwrap();
+                if let Err(e) = socket.send(Message::Text(encoded)).await {
+                    log::error!("Error sending energy: {}", e);
+                    break;
+                }
+                energy_sent = Some(meter);
             }
         }
     }
     log::info!("Admin disconnected from room");
 }
 
+pub async fn handle_audience_request(
+    State(state): State<ApiState>,
+    ws: WebSocketUpgrade,
+    Path(room_id): Path<RoomID>,
+) -> Result<Response, (StatusCode, &'static str)> {
+    match state.audience_channels.subscribe(room_id) {
+        Some(receiver) => Ok(ws
+            .on_upgrade(move |socket| handle_audience_websocket(socket, state, room_id, receiver))),
+        None => Err((StatusCode::NOT_FOUND, "Room not found")),
+    }
+}
+
+async fn handle_audience_websocket(
+    mut socket: WebSocket,
+    state: ApiState,
+    room_id: RoomID,
+    mut audience_receiver: ReceiverGuard<AudienceEvent>,
+) {
+    // Check the first message is the token of a user of the room
+    let future = timeout(Duration::from_secs(3), socket.recv());
+    let user = match future.await {
+        Err(_) => {
+            log::warn!("Audience socket auth timed out. (closing it)");
+            let close_frame = CloseFrame {
+                code: 4002,
+                reason: "Auth timed out".into(),
+            };
+            socket.send(Message::Close(Some(close_frame))).await.ok();
+            return;
+        }
+        Ok(Some(Ok(Message::Text(token)))) => match jwt::verify(token.trim()) {
+            Ok(user) => match has_room_access(&state.db, &user, room_id).await {
+                Ok(true) => Some(user),
+                Ok(false) => None,
+                Err(e) => {
+                    log::error!("Error checking the audience socket access: {}", e);
+                    None
+                }
+            },
+            Err(e) => {
+                log::warn!("Error verifying audience websocket auth token: {}", e);
+                None
+            }
+        },
+        Ok(msg) => {
+            log::warn!("Received invalid audience auth message: {:?}", msg);
+            None
+        }
+    };
+    let Some(user) = user else {
+        let close_frame = CloseFrame {
+            code: 4001,
+            reason: "Auth Failed".into(),
+        };
+        socket.send(Message::Close(Some(close_frame))).await.ok();
+        return;
+    };
+    state.record_activity(room_id, &user);
+
+    loop {
+        select! {
+            msg = socket.recv() => {
+                match msg {
+                    Some(Ok(Message::Close(_)) | Err(_)) | None => break,
+                    // The users don't send anything after the auth token
+                    msg => log::warn!("Received invalid audience websocket message: {:?}", msg),
+                }
+            }
+            Ok(event) = audience_receiver.recv() => {
+                let Some(message) = event.message_for(user.uid) else {
+                    continue;
+                };
+                let encoded = serde_json::to_string(&message).unwrap();
+                if let Err(e) = socket.send(Message::Text(encoded)).await {
+                    log::error!("Error sending audience event: {}", e);
+                    break;
+                }
+            }
+        }
+    }
+}
+
 #[allow(clippy::needless_return)]
 fn is_admin(msg: Option<Result<Message, Error>>) -> bool {
     match msg {
@@ -1,10 +1,10 @@
-use axum::Router;
+use axum::{routing::get, Router};
 use migration::{Migrator, MigratorTrait};
 use sea_orm::Database;
 use tokio::signal;
 use tower_http::trace::TraceLayer;
 
-use crate::api::state::ApiState;
+use crate::{api::state::ApiState, utils::client_ip::TrustedProxies};
 
 mod api;
 #[cfg(feature = "embed-ui")]
@@ -34,12 +34,32 @@ async fn main() {
         .await
         .expect("Failed to migrate database");
 
-    let state = ApiState::new(db, admin_username, admin_password);
+    // The public URL is optional, the request host is used if not set
+    let public_url = std::env::var("PUBLIC_URL").ok();
+
+    // The reverse proxies allowed to give the address of the users, none by default
+    let trusted_proxies = std::env::var("TRUSTED_PROXIES")
+        .map(|proxies| TrustedProxies::parse(&proxies).expect("Invalid TRUSTED_PROXIES"))
+        .unwrap_or_default();
+
+    let state = ApiState::new(
+        db,
+        admin_username,
+        admin_password,
+        public_url,
+        trusted_proxies,
+    );
+    tokio::spawn(state.clone().sweep_presence());
+    tokio::spawn(state.clone().sweep_vote_guard());
+    tokio::spawn(state.clone().backfill_explicit());
+    tokio::spawn(state.clone().schedule_open_polls());
 
     let api = api::router(state);
     let api = api.layer(TraceLayer::new_for_http());
     let api = utils::cors::init(api);
-    let app = Router::new().nest("/api", api);
+    let app = Router::new()
+        .nest("/api", api)
+        .route("/j/:room", get(api::share::join_redirect));
     #[cfg(feature = "embed-ui")]
     let app = ui::mount(app);
 
@@ -51,7 +71,9 @@ async fn main() {
         .unwrap();
 
     #[cfg(not(feature = "https"))]
-    let server = axum::Server::bind(&addr).serve(app.into_make_service());
+    // The client address is used to rate limit the votes
+    let server = axum::Server::bind(&addr)
+        .serve(app.into_make_service_with_connect_info::<std::net::SocketAddr>());
 
     #[cfg(feature = "https")]
     let server = utils::https::run_https_server(addr, app);
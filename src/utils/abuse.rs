@@ -0,0 +1,131 @@
+use std::{
+    collections::{HashMap, HashSet},
+    net::IpAddr,
+    time::{Duration, Instant},
+};
+
+use uuid::Uuid;
+
+use super::room_id::RoomID;
+
+/// A user is fresh during this time after their first vote.
+const FRESH_USER_DURATION: Duration = Duration::from_secs(10 * 60);
+/// The users are forgotten after this time without voting, they are fresh again if they come back.
+const USER_RETENTION: Duration = Duration::from_secs(60 * 60);
+/// The period of the votes counted in a burst.
+const BURST_WINDOW: Duration = Duration::from_secs(5 * 60);
+/// The number of fresh users of the same address voting the same music to be flagged.
+const BURST_USERS: usize = 5;
+
+/// The fresh users of an address who voted a music, with the date of their vote.
+type Burst = HashMap<Uuid, Instant>;
+
+/// Detect bursts of fresh users from the same address voting the same music,
+/// as a script joining the room again and again would do.
+#[derive(Default)]
+pub struct AbuseDetector {
+    /// The dates of the first and last votes of each user
+    users: HashMap<Uuid, (Instant, Instant)>,
+    bursts: HashMap<(RoomID, IpAddr, i64), Burst>,
+    /// The bursts already flagged, flagged again once they end
+    flagged: HashSet<(RoomID, IpAddr, i64)>,
+}
+
+impl AbuseDetector {
+    /// Record a vote.
+    ///
+    /// Returns the number of fresh users of the burst when the vote starts a burst.
+    pub fn record(
+        &mut self,
+        room_id: RoomID,
+        uid: Uuid,
+        ip: IpAddr,
+        music_id: i64,
+        now: Instant,
+    ) -> Option<usize> {
+        let (first_vote, last_vote) = self.users.entry(uid).or_insert((now, now));
+        *last_vote = now;
+        if now.duration_since(*first_vote) >= FRESH_USER_DURATION {
+            return None;
+        }
+
+        let key = (room_id, ip, music_id);
+        let burst = self.bursts.entry(key).or_default();
+        burst.insert(uid, now);
+        burst.retain(|_, date| now.duration_since(*date) < BURST_WINDOW);
+
+        if burst.len() < BURST_USERS {
+            self.flagged.remove(&key);
+            return None;
+        }
+        match self.flagged.insert(key) {
+            true => Some(burst.len()),
+            false => None,
+        }
+    }
+
+    /// Forget the inactive users and the ended bursts.
+    pub fn sweep(&mut self, now: Instant) {
+        self.users
+            .retain(|_, (_, last_vote)| now.duration_since(*last_vote) < USER_RETENTION);
+        self.bursts.retain(|_, burst| {
+            burst.retain(|_, date| now.duration_since(*date) < BURST_WINDOW);
+            !burst.is_empty()
+        });
+        let bursts = &self.bursts;
+        self.flagged.retain(|key| bursts.contains_key(key));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+
+    use super::*;
+
+    const ROOM: RoomID = RoomID::new(1);
+    const IP: IpAddr = IpAddr::V4(Ipv4Addr::LOCALHOST);
+
+    #[test]
+    fn test_burst_of_fresh_users() {
+        let start = Instant::now();
+        let mut detector = AbuseDetector::default();
+
+        for i in 0..BURST_USERS - 1 {
+            let now = start + Duration::from_secs(i as u64);
+            assert_eq!(detector.record(ROOM, Uuid::new_v4(), IP, 42, now), None);
+        }
+        // Another music or another address is another burst
+        assert_eq!(detector.record(ROOM, Uuid::new_v4(), IP, 7, start), None);
+        let other_ip = IpAddr::V4(Ipv4Addr::BROADCAST);
+        assert_eq!(
+            detector.record(ROOM, Uuid::new_v4(), other_ip, 42, start),
+            None
+        );
+
+        let now = start + Duration::from_secs(10);
+        assert_eq!(
+            detector.record(ROOM, Uuid::new_v4(), IP, 42, now),
+            Some(BURST_USERS)
+        );
+        // The burst is flagged once
+        assert_eq!(detector.record(ROOM, Uuid::new_v4(), IP, 42, now), None);
+    }
+
+    #[test]
+    fn test_old_users_are_not_counted() {
+        let start = Instant::now();
+        let mut detector = AbuseDetector::default();
+
+        let users: Vec<Uuid> = (0..BURST_USERS).map(|_| Uuid::new_v4()).collect();
+        for &uid in &users {
+            detector.record(ROOM, uid, IP, 1, start);
+        }
+
+        let later = start + FRESH_USER_DURATION;
+        detector.sweep(later);
+        for &uid in &users {
+            assert_eq!(detector.record(ROOM, uid, IP, 42, later), None);
+        }
+    }
+}
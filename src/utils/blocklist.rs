@@ -0,0 +1,107 @@
+use entity::block_rule::{self, BlockKind};
+
+/// The block rules applying to a room, its own and the global ones.
+pub struct Blocklist {
+    rules: Vec<block_rule::Model>,
+}
+
+impl Blocklist {
+    pub fn new(rules: Vec<block_rule::Model>) -> Self {
+        Self { rules }
+    }
+
+    /// Check if a music is blocked by any rule.
+    pub fn blocks(&self, music_id: i64, title: &str, artist: &str) -> bool {
+        self.rules.iter().any(|rule| match rule.kind {
+            BlockKind::Track => rule.value.trim() == music_id.to_string(),
+            BlockKind::Artist => rule.value.trim().to_lowercase() == artist.to_lowercase(),
+            BlockKind::Title => matches_pattern(&rule.value, title),
+            BlockKind::Nickname => false,
+        })
+    }
+
+    /// Check if a nickname is blocked by any rule.
+    pub fn blocks_nickname(&self, nickname: &str) -> bool {
+        self.rules
+            .iter()
+            .any(|rule| rule.kind == BlockKind::Nickname && matches_pattern(&rule.value, nickname))
+    }
+}
+
+/// Match the whole text against a pattern where `*` matches any text, ignoring the case.
+pub fn matches_pattern(pattern: &str, text: &str) -> bool {
+    let pattern = pattern.trim().to_lowercase();
+    let text = text.to_lowercase();
+
+    let mut parts = pattern.split('*');
+    // There is always a first part, empty if the pattern starts with `*`
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = text.strip_prefix(first) else {
+        return false;
+    };
+
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        // No `*` in the pattern
+        return rest.is_empty();
+    };
+
+    for part in middle {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+
+    rest.ends_with(last)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+
+    use super::*;
+
+    fn rule(kind: BlockKind, value: &str) -> block_rule::Model {
+        block_rule::Model {
+            id: 0,
+            room_id: None,
+            kind,
+            value: value.to_string(),
+            creation_date: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_matches_pattern() {
+        assert!(matches_pattern("Baby Shark", "baby shark"));
+        assert!(!matches_pattern("Baby Shark", "Baby Shark (Remix)"));
+        assert!(matches_pattern("baby shark*", "Baby Shark (Remix)"));
+        assert!(matches_pattern("*shark*", "Baby Shark Dance"));
+        assert!(matches_pattern("*", "anything"));
+        assert!(matches_pattern("a*b*c", "a-b-c"));
+        assert!(!matches_pattern("a*b*c", "a-c-b"));
+        assert!(!matches_pattern("*ab*ab", "xab"));
+        assert!(matches_pattern("*ab*ab", "xabab"));
+    }
+
+    #[test]
+    fn test_blocks() {
+        let blocklist = Blocklist::new(vec![
+            rule(BlockKind::Track, "42"),
+            rule(BlockKind::Artist, "Pinkfong"),
+            rule(BlockKind::Title, "*macarena*"),
+            rule(BlockKind::Nickname, "*admin*"),
+        ]);
+
+        assert!(blocklist.blocks(42, "Any title", "Any artist"));
+        assert!(blocklist.blocks(1, "Baby Shark", "PINKFONG"));
+        assert!(blocklist.blocks(2, "Macarena (Bayside Boys Remix)", "Los del Rio"));
+        assert!(!blocklist.blocks(3, "Around the World", "Daft Punk"));
+        assert!(!Blocklist::new(Vec::new()).blocks(42, "", ""));
+        // The nickname rules only apply to the nicknames
+        assert!(!blocklist.blocks(4, "Admin", "Admin"));
+        assert!(blocklist.blocks_nickname("The ADMIN"));
+        assert!(!blocklist.blocks_nickname("Pinkfong"));
+    }
+}
@@ -0,0 +1,180 @@
+use std::{
+    net::{AddrParseError, IpAddr, SocketAddr},
+    sync::Arc,
+};
+
+use axum::{
+    extract::{ConnectInfo, FromRef, FromRequestParts},
+    http::{request::Parts, HeaderMap, StatusCode},
+};
+
+/// The addresses of the reverse proxies allowed to give the address of the clients.
+#[derive(Debug, Clone, Default)]
+pub struct TrustedProxies(Arc<[IpAddr]>);
+
+impl TrustedProxies {
+    /// Parse a comma separated list of addresses.
+    pub fn parse(list: &str) -> Result<Self, AddrParseError> {
+        let proxies = list
+            .split(',')
+            .map(str::trim)
+            .filter(|proxy| !proxy.is_empty())
+            .map(str::parse)
+            .collect::<Result<Vec<IpAddr>, _>>()?;
+        Ok(Self(proxies.into()))
+    }
+
+    fn contains(&self, ip: IpAddr) -> bool {
+        self.0.contains(&ip)
+    }
+
+    /// Get the address of the client, given by the `Forwarded` or `X-Forwarded-For` header
+    /// if the peer is a trusted proxy.
+    ///
+    /// The forwarded addresses are read from the last one, skipping the trusted proxies,
+    /// as the first ones can be forged by the client.
+    pub fn client_ip(&self, peer: IpAddr, headers: &HeaderMap) -> IpAddr {
+        if !self.contains(peer) {
+            return peer;
+        }
+
+        let mut client = peer;
+        for hop in forwarded_chain(headers).iter().rev() {
+            let Some(ip) = parse_hop(hop) else {
+                break;
+            };
+            client = ip;
+            if !self.contains(ip) {
+                break;
+            }
+        }
+        client
+    }
+}
+
+/// The forwarded addresses, the client first and the last proxy last.
+fn forwarded_chain(headers: &HeaderMap) -> Vec<String> {
+    let values = |name| {
+        headers
+            .get_all(name)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .map(str::trim)
+            .collect::<Vec<_>>()
+    };
+
+    let forwarded = values("forwarded");
+    if !forwarded.is_empty() {
+        // The `for` parameter of each element of the standard header
+        return forwarded
+            .into_iter()
+            .map(|element| {
+                element
+                    .split(';')
+                    .map(str::trim)
+                    .find_map(|pair| {
+                        let (key, value) = pair.split_once('=')?;
+                        key.eq_ignore_ascii_case("for").then_some(value)
+                    })
+                    .unwrap_or_default()
+                    .to_string()
+            })
+            .collect();
+    }
+
+    values("x-forwarded-for")
+        .into_iter()
+        .map(str::to_string)
+        .collect()
+}
+
+/// Parse a forwarded address, with an optional port and quotes.
+fn parse_hop(hop: &str) -> Option<IpAddr> {
+    let hop = hop.trim().trim_matches('"');
+    if let Some(rest) = hop.strip_prefix('[') {
+        // An IPv6 address, with a port or not
+        return rest.split(']').next()?.parse().ok();
+    }
+    hop.parse()
+        .ok()
+        .or_else(|| hop.parse::<SocketAddr>().ok().map(|addr| addr.ip()))
+}
+
+/// The address of the client, behind the trusted proxies.
+pub struct ClientIp(pub IpAddr);
+
+#[axum::async_trait]
+impl<S> FromRequestParts<S> for ClientIp
+where
+    S: Send + Sync,
+    TrustedProxies: FromRef<S>,
+{
+    type Rejection = StatusCode;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let Some(&ConnectInfo(peer)) = parts.extensions.get::<ConnectInfo<SocketAddr>>() else {
+            log::error!("Missing connection info, the server must be started with it");
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        };
+
+        let proxies = TrustedProxies::from_ref(state);
+        Ok(Self(proxies.client_ip(peer.ip(), &parts.headers)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::HeaderValue;
+
+    use super::*;
+
+    fn ip(ip: &str) -> IpAddr {
+        ip.parse().unwrap()
+    }
+
+    fn headers(name: &'static str, value: &'static str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(name, HeaderValue::from_static(value));
+        headers
+    }
+
+    #[test]
+    fn test_untrusted_peer() {
+        let proxies = TrustedProxies::parse("10.0.0.1").unwrap();
+        let headers = headers("x-forwarded-for", "1.2.3.4");
+        assert_eq!(proxies.client_ip(ip("5.6.7.8"), &headers), ip("5.6.7.8"));
+        assert_eq!(
+            TrustedProxies::default().client_ip(ip("10.0.0.1"), &headers),
+            ip("10.0.0.1")
+        );
+    }
+
+    #[test]
+    fn test_forwarded_for() {
+        let proxies = TrustedProxies::parse("10.0.0.1, 10.0.0.2").unwrap();
+        // The first address is forged by the client
+        let headers = headers("x-forwarded-for", "6.6.6.6, 1.2.3.4, 10.0.0.2");
+        assert_eq!(proxies.client_ip(ip("10.0.0.1"), &headers), ip("1.2.3.4"));
+        assert_eq!(
+            proxies.client_ip(ip("10.0.0.1"), &HeaderMap::new()),
+            ip("10.0.0.1")
+        );
+    }
+
+    #[test]
+    fn test_forwarded() {
+        let proxies = TrustedProxies::parse("10.0.0.1").unwrap();
+        let headers = headers(
+            "forwarded",
+            r#"for=6.6.6.6, for="[2001:db8::1]:4711";proto=https"#,
+        );
+        assert_eq!(
+            proxies.client_ip(ip("10.0.0.1"), &headers),
+            ip("2001:db8::1")
+        );
+
+        let headers = self::headers("forwarded", "for=1.2.3.4:5678;by=10.0.0.1");
+        assert_eq!(proxies.client_ip(ip("10.0.0.1"), &headers), ip("1.2.3.4"));
+    }
+}
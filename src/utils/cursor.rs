@@ -0,0 +1,53 @@
+use serde::{de::DeserializeOwned, Serialize};
+
+/// Encode the position of a page as an opaque cursor, the hexadecimal of its JSON.
+pub fn encode<T: Serialize>(position: &T) -> String {
+    let json = serde_json::to_vec(position).expect("The cursors can be serialized");
+    json.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Decode a cursor made by [`encode`], `None` if it is invalid.
+pub fn decode<T: DeserializeOwned>(cursor: &str) -> Option<T> {
+    if !cursor.len().is_multiple_of(2) || !cursor.is_ascii() {
+        return None;
+    }
+
+    let json = (0..cursor.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&cursor[i..i + 2], 16).ok())
+        .collect::<Option<Vec<u8>>>()?;
+    serde_json::from_slice(&json).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use super::*;
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Position {
+        score: f64,
+        title: String,
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let position = Position {
+            score: 0.1 + 0.2,
+            title: "Déjà vu".to_string(),
+        };
+        let cursor = encode(&position);
+        assert!(cursor.chars().all(|c| c.is_ascii_hexdigit()));
+        assert_eq!(decode::<Position>(&cursor), Some(position));
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert_eq!(decode::<Position>(""), None);
+        assert_eq!(decode::<Position>("7b2"), None);
+        assert_eq!(decode::<Position>("zz"), None);
+        assert_eq!(decode::<Position>("éé"), None);
+        assert_eq!(decode::<Position>(&encode(&42)), None);
+    }
+}
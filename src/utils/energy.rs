@@ -0,0 +1,110 @@
+use chrono::{DateTime, Duration, Utc};
+use entity::signal::SignalKind;
+use serde::{Deserialize, Serialize};
+
+/// The signals of the crowd counted in a meter.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EnergyMeter {
+    pub more_energy: u32,
+    pub less_energy: u32,
+    pub faster: u32,
+    pub slower: u32,
+    /// The energy wanted, more energy minus less energy
+    pub energy: i32,
+    /// The tempo wanted, faster minus slower
+    pub tempo: i32,
+}
+
+impl EnergyMeter {
+    pub fn new(signals: impl IntoIterator<Item = SignalKind>) -> Self {
+        let mut meter = Self::default();
+        for kind in signals {
+            match kind {
+                SignalKind::MoreEnergy => meter.more_energy += 1,
+                SignalKind::LessEnergy => meter.less_energy += 1,
+                SignalKind::Faster => meter.faster += 1,
+                SignalKind::Slower => meter.slower += 1,
+            }
+        }
+        meter.energy = meter.more_energy as i32 - meter.less_energy as i32;
+        meter.tempo = meter.faster as i32 - meter.slower as i32;
+        meter
+    }
+}
+
+/// The meter of the signals sent during a period.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EnergyPoint {
+    pub start: DateTime<Utc>,
+    #[serde(flatten)]
+    pub meter: EnergyMeter,
+}
+
+/// Split the signals sent from `start` to `end` in periods of `step`,
+/// the last period ending at `end` even if it is shorter.
+pub fn time_series(
+    signals: &[(DateTime<Utc>, SignalKind)],
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    step: Duration,
+) -> Vec<EnergyPoint> {
+    let mut points = Vec::new();
+    let mut period_start = start;
+    while period_start < end {
+        let period_end = (period_start + step).min(end);
+        let meter = EnergyMeter::new(
+            signals
+                .iter()
+                .filter(|(date, _)| *date >= period_start && *date < period_end)
+                .map(|(_, kind)| *kind),
+        );
+        points.push(EnergyPoint {
+            start: period_start,
+            meter,
+        });
+        period_start = period_end;
+    }
+    points
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    #[test]
+    fn test_meter() {
+        let meter = EnergyMeter::new([
+            SignalKind::MoreEnergy,
+            SignalKind::MoreEnergy,
+            SignalKind::LessEnergy,
+            SignalKind::Slower,
+        ]);
+        assert_eq!(meter.energy, 1);
+        assert_eq!(meter.tempo, -1);
+        assert_eq!(meter.more_energy, 2);
+        assert_eq!(EnergyMeter::new([]), EnergyMeter::default());
+    }
+
+    #[test]
+    fn test_time_series() {
+        let start = Utc.with_ymd_and_hms(2023, 6, 1, 23, 0, 0).unwrap();
+        let at = |minutes| start + Duration::minutes(minutes);
+        let signals = [
+            (at(0), SignalKind::Faster),
+            (at(4), SignalKind::Faster),
+            (at(5), SignalKind::MoreEnergy),
+            (at(12), SignalKind::LessEnergy),
+            // After the end
+            (at(13), SignalKind::LessEnergy),
+        ];
+
+        let points = time_series(&signals, start, at(13), Duration::minutes(5));
+        let starts: Vec<_> = points.iter().map(|p| p.start).collect();
+        assert_eq!(starts, [at(0), at(5), at(10)]);
+        assert_eq!(points[0].meter.tempo, 2);
+        assert_eq!(points[1].meter.energy, 1);
+        assert_eq!(points[2].meter.energy, -1);
+    }
+}
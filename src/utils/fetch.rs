@@ -0,0 +1,80 @@
+use std::{
+    collections::{HashMap, HashSet},
+    future::Future,
+    hash::Hash,
+};
+
+use tokio::task::JoinSet;
+
+/// Fetch the value of each key concurrently, the duplicate keys being fetched once.
+pub async fn fetch_unique<K, T, F, Fut>(
+    keys: impl IntoIterator<Item = K>,
+    fetch: F,
+) -> HashMap<K, T>
+where
+    K: Eq + Hash + Copy + Send + 'static,
+    T: Send + 'static,
+    F: Fn(K) -> Fut,
+    Fut: Future<Output = T> + Send + 'static,
+{
+    let keys: HashSet<K> = keys.into_iter().collect();
+
+    let mut tasks = JoinSet::new();
+    for key in keys {
+        let future = fetch(key);
+        tasks.spawn(async move { (key, future.await) });
+    }
+
+    let mut values = HashMap::new();
+    while let Some(result) = tasks.join_next().await {
+        match result {
+            Ok((key, value)) => {
+                values.insert(key, value);
+            }
+            Err(e) => log::error!("Failed to join a fetch task: {}", e),
+        }
+    }
+    values
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        time::Duration,
+    };
+
+    use tokio::{sync::Barrier, time::timeout};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_fetch_unique() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        // Only passed if the three keys are fetched at the same time
+        let barrier = Arc::new(Barrier::new(3));
+
+        let fetch = |key: i64| {
+            let calls = calls.clone();
+            let barrier = barrier.clone();
+            async move {
+                calls.fetch_add(1, Ordering::SeqCst);
+                barrier.wait().await;
+                (key != 2).then_some(key * 10)
+            }
+        };
+
+        let values = timeout(Duration::from_secs(5), fetch_unique([1, 2, 1, 3, 3], fetch))
+            .await
+            .expect("The keys were not fetched concurrently");
+
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+        assert_eq!(values.len(), 3);
+        assert_eq!(values[&1], Some(10));
+        assert_eq!(values[&2], None);
+        assert_eq!(values[&3], Some(30));
+    }
+}
@@ -0,0 +1,150 @@
+use serde::Deserialize;
+
+use super::{
+    pdf::{text_width, Document, Font, Page, PageSize},
+    qr::QrImage,
+};
+
+/// The layout of the printed flyers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FlyerTemplate {
+    /// A6 table cards, as many as possible on each page with cut lines.
+    #[default]
+    TableCard,
+    /// One flyer filling the whole page.
+    Poster,
+}
+
+/// The content of a room flyer.
+pub struct Flyer<'a> {
+    pub title: &'a str,
+    pub subtitle: &'a str,
+    pub blurb: &'a str,
+    pub url: &'a str,
+    pub qr_code: &'a QrImage,
+}
+
+impl Flyer<'_> {
+    /// Render the flyer on a page of the given size.
+    pub fn render(&self, template: FlyerTemplate, page_size: PageSize) -> Document {
+        let mut page = Page::new(page_size);
+
+        match template {
+            FlyerTemplate::Poster => {
+                let (width, height) = (page.width(), page.height());
+                self.draw(&mut page, 0.0, 0.0, width, height);
+            }
+            FlyerTemplate::TableCard => {
+                let (card_width, card_height) = PageSize::A6.dimensions();
+                let (cols, rows) = card_grid(page_size);
+                let (cols, rows) = (cols as f32, rows as f32);
+                // Center the grid of cards on the page
+                let offset_x = (page.width() - cols * card_width).max(0.0) / 2.0;
+                let offset_y = (page.height() - rows * card_height).max(0.0) / 2.0;
+
+                for row in 0..rows as u32 {
+                    for col in 0..cols as u32 {
+                        let x = offset_x + col as f32 * card_width;
+                        let y = offset_y + row as f32 * card_height;
+                        page.stroke_rect(x, y, card_width, card_height);
+                        self.draw(&mut page, x, y, card_width, card_height);
+                    }
+                }
+            }
+        }
+
+        let mut document = Document::new();
+        document.add_page(page);
+        document
+    }
+
+    /// Draw the flyer in the box, `x` and `y` being its bottom left corner.
+    fn draw(&self, page: &mut Page, x: f32, y: f32, width: f32, height: f32) {
+        let margin = width * 0.08;
+        let inner_width = width - 2.0 * margin;
+        let center = x + width / 2.0;
+
+        let title_size = fit(self.title, width * 0.08, inner_width);
+        let mut cursor = y + height - margin - title_size;
+        page.text_centered(Font::Bold, title_size, center, cursor, self.title);
+
+        let subtitle_size = fit(self.subtitle, width * 0.055, inner_width);
+        cursor -= subtitle_size * 1.8;
+        page.text_centered(Font::Regular, subtitle_size, center, cursor, self.subtitle);
+
+        let qr_size = inner_width.min(height * 0.5);
+        cursor -= subtitle_size + qr_size;
+        page.qr_code(self.qr_code, center - qr_size / 2.0, cursor, qr_size);
+
+        let url_size = fit(self.url, width * 0.035, inner_width);
+        let bottom = y + margin + url_size * 2.0;
+        page.text_centered(Font::Regular, url_size, center, y + margin, self.url);
+
+        let blurb_size = width * 0.05;
+        cursor -= blurb_size * 0.5;
+        for line in wrap(self.blurb, blurb_size, inner_width) {
+            cursor -= blurb_size * 1.3;
+            if cursor < bottom {
+                break;
+            }
+            page.text_centered(Font::Regular, blurb_size, center, cursor, &line);
+        }
+    }
+}
+
+/// The number of columns and rows of A6 table cards fitting on the page, at least one.
+fn card_grid(page_size: PageSize) -> (u32, u32) {
+    // The rounding of the sizes must not lose a card, two A6 being exactly as wide as an A4
+    const EPSILON: f32 = 0.01;
+
+    let (width, height) = page_size.dimensions();
+    let (card_width, card_height) = PageSize::A6.dimensions();
+    let cols = (width / card_width + EPSILON).floor().max(1.0);
+    let rows = (height / card_height + EPSILON).floor().max(1.0);
+    (cols as u32, rows as u32)
+}
+
+/// Reduce the font size so the text fits in the width.
+fn fit(text: &str, size: f32, width: f32) -> f32 {
+    let text_width = text_width(text, size);
+    if text_width > width {
+        size * width / text_width
+    } else {
+        size
+    }
+}
+
+/// Split the text in lines fitting in the width.
+fn wrap(text: &str, size: f32, width: f32) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        let candidate = match line.is_empty() {
+            true => word.to_string(),
+            false => format!("{line} {word}"),
+        };
+        if text_width(&candidate, size) > width && !line.is_empty() {
+            lines.push(std::mem::replace(&mut line, word.to_string()));
+        } else {
+            line = candidate;
+        }
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_card_grid() {
+        assert_eq!(card_grid(PageSize::A4), (2, 2));
+        assert_eq!(card_grid(PageSize::A5), (1, 1));
+        assert_eq!(card_grid(PageSize::A6), (1, 1));
+        assert_eq!(card_grid(PageSize::Letter), (2, 1));
+    }
+}
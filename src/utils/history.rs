@@ -0,0 +1,142 @@
+use std::fmt::Write;
+
+use chrono::{DateTime, Duration, DurationRound, Utc};
+use entity::vote::VoteValue;
+use serde::{Deserialize, Serialize};
+
+/// The votes cast during a period.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VotePoint {
+    pub start: DateTime<Utc>,
+    /// Every vote cast, the retracted ones included
+    pub votes: u32,
+    pub likes: u32,
+    pub dislikes: u32,
+    pub retracted: u32,
+}
+
+/// The start of the period of `step` containing the date, the periods being aligned on the
+/// Unix epoch.
+pub fn period_start(date: DateTime<Utc>, step: Duration) -> DateTime<Utc> {
+    date.duration_trunc(step).unwrap_or(date)
+}
+
+/// Split the votes cast from `start` to `end` in periods of `step`,
+/// the last period ending at `end` even if it is shorter.
+///
+/// The votes must be sorted by date.
+pub fn vote_series(
+    votes: &[(DateTime<Utc>, VoteValue)],
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    step: Duration,
+) -> Vec<VotePoint> {
+    let mut votes = votes
+        .iter()
+        .skip_while(|(date, _)| *date < start)
+        .peekable();
+    let mut points = Vec::new();
+    let mut period_start = start;
+    while period_start < end {
+        let period_end = (period_start + step).min(end);
+        let mut point = VotePoint {
+            start: period_start,
+            votes: 0,
+            likes: 0,
+            dislikes: 0,
+            retracted: 0,
+        };
+        while let Some((_, value)) = votes.next_if(|(date, _)| *date < period_end) {
+            point.votes += 1;
+            match value {
+                VoteValue::Like => point.likes += 1,
+                VoteValue::Dislike => point.dislikes += 1,
+                VoteValue::Neutral => point.retracted += 1,
+            }
+        }
+        points.push(point);
+        period_start = period_end;
+    }
+    points
+}
+
+/// Format the points as CSV, with a header line.
+pub fn to_csv(points: &[VotePoint]) -> String {
+    let mut csv = String::from("start,votes,likes,dislikes,retracted\n");
+    for point in points {
+        // Writing to a String can't fail
+        let _ = writeln!(
+            csv,
+            "{},{},{},{},{}",
+            point.start.to_rfc3339(),
+            point.votes,
+            point.likes,
+            point.dislikes,
+            point.retracted
+        );
+    }
+    csv
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn start() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2023, 6, 1, 23, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn test_vote_series() {
+        let at = |seconds| start() + Duration::seconds(seconds);
+        let votes = [
+            (at(-10), VoteValue::Like),
+            (at(0), VoteValue::Like),
+            (at(59), VoteValue::Dislike),
+            (at(60), VoteValue::Neutral),
+            (at(150), VoteValue::Like),
+            (at(200), VoteValue::Like),
+        ];
+
+        let points = vote_series(&votes, start(), at(180), Duration::minutes(1));
+        let counts: Vec<_> = points
+            .iter()
+            .map(|p| (p.votes, p.likes, p.dislikes, p.retracted))
+            .collect();
+        assert_eq!(counts, [(2, 1, 1, 0), (1, 0, 0, 1), (1, 1, 0, 0)]);
+        assert_eq!(points[2].start, at(120));
+    }
+
+    #[test]
+    fn test_period_start() {
+        let date = start() + Duration::seconds(14 * 60 + 42);
+        assert_eq!(
+            period_start(date, Duration::minutes(1)),
+            start() + Duration::minutes(14)
+        );
+        assert_eq!(period_start(date, Duration::minutes(15)), start());
+        assert_eq!(
+            period_start(date, Duration::minutes(24 * 60)),
+            start() - Duration::hours(23)
+        );
+        assert_eq!(period_start(start(), Duration::minutes(60)), start());
+    }
+
+    #[test]
+    fn test_to_csv() {
+        let points = vote_series(
+            &[(start(), VoteValue::Like)],
+            start(),
+            start() + Duration::minutes(2),
+            Duration::minutes(1),
+        );
+        assert_eq!(
+            to_csv(&points),
+            "start,votes,likes,dislikes,retracted\n\
+             2023-06-01T23:00:00+00:00,1,1,0,0\n\
+             2023-06-01T23:01:00+00:00,0,0,0,0\n"
+        );
+    }
+}
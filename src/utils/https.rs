@@ -27,7 +27,7 @@ pub async fn run_https_server(addr: SocketAddr, app: Router) {
 
             if let Ok(config) = RustlsConfig::from_pem_file(cert_path, key_path).await {
                 axum_server::bind_rustls(addr, config)
-                    .serve(app.into_make_service())
+                    .serve(app.into_make_service_with_connect_info::<SocketAddr>())
                     .await
                     .ok();
             } else {
@@ -39,7 +39,7 @@ pub async fn run_https_server(addr: SocketAddr, app: Router) {
             log::info!("No TLS certificate provided, using HTTP");
 
             axum::Server::bind(&addr)
-                .serve(app.into_make_service())
+                .serve(app.into_make_service_with_connect_info::<SocketAddr>())
                 .await
                 .ok();
         }
@@ -16,7 +16,13 @@ use super::room_id::RoomID;
 #[serde(tag = "role")]
 pub enum Role {
     Admin,
-    User { room_id: RoomID },
+    User {
+        room_id: RoomID,
+    },
+    /// A user of an event, allowed in every room of the event.
+    Event {
+        event_id: RoomID,
+    },
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
@@ -34,6 +40,13 @@ impl User {
         }
     }
 
+    pub fn new_event_user(event_id: RoomID) -> Self {
+        Self {
+            uid: Uuid::new_v4(),
+            role: Role::Event { event_id },
+        }
+    }
+
     pub fn new_admin() -> Self {
         Self {
             uid: Uuid::from_u128(0), // TODO: should it be a secret?
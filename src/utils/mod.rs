@@ -1,8 +1,23 @@
+pub mod abuse;
+pub mod blocklist;
+pub mod client_ip;
 pub mod cors;
+pub mod cursor;
+pub mod energy;
+pub mod fetch;
+pub mod flyer;
+pub mod history;
 #[cfg(feature = "https")]
 pub mod https;
 pub mod jwt;
+pub mod nickname;
+pub mod pdf;
+pub mod profanity;
+pub mod qr;
+pub mod ranking;
+pub mod rate_limit;
 pub mod room_id;
+pub mod throttle;
 
 /// Macro to get environment variables and exit if any are missing.
 ///
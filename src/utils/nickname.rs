@@ -0,0 +1,43 @@
+/// The minimum number of characters of a nickname.
+pub const MIN_NICKNAME_LENGTH: usize = 2;
+/// The maximum number of characters of a nickname.
+pub const MAX_NICKNAME_LENGTH: usize = 24;
+
+/// Clean a nickname, returning `None` if it is invalid.
+///
+/// The spaces are trimmed and collapsed, and only letters, digits, spaces, `-`, `_` and `.` are allowed.
+pub fn clean_nickname(nickname: &str) -> Option<String> {
+    let nickname = nickname.split_whitespace().collect::<Vec<_>>().join(" ");
+
+    let length = nickname.chars().count();
+    let valid_chars = nickname
+        .chars()
+        .all(|c| c.is_alphanumeric() || matches!(c, ' ' | '-' | '_' | '.'));
+
+    let valid = (MIN_NICKNAME_LENGTH..=MAX_NICKNAME_LENGTH).contains(&length) && valid_chars;
+    valid.then_some(nickname)
+}
+
+/// The key of a nickname, two nicknames with the same key being the same for the users.
+pub fn nickname_key(nickname: &str) -> String {
+    nickname.to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clean_nickname() {
+        assert_eq!(
+            clean_nickname("  Julie   B. "),
+            Some("Julie B.".to_string())
+        );
+        assert_eq!(clean_nickname("dj_ZoÉ-42"), Some("dj_ZoÉ-42".to_string()));
+        assert_eq!(clean_nickname("a"), None);
+        assert_eq!(clean_nickname("   "), None);
+        assert_eq!(clean_nickname("<script>"), None);
+        assert_eq!(clean_nickname(&"a".repeat(25)), None);
+        assert_eq!(nickname_key("Julie B."), nickname_key("JULIE b."));
+    }
+}
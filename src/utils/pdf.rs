@@ -0,0 +1,277 @@
+use std::io::Write;
+
+use serde::Deserialize;
+
+use super::qr::QrImage;
+
+/// A page size, in PDF points (1/72 inch).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PageSize {
+    A4,
+    A5,
+    A6,
+    Letter,
+}
+
+impl PageSize {
+    /// The width and height of the page in portrait orientation.
+    pub fn dimensions(&self) -> (f32, f32) {
+        match self {
+            PageSize::A4 => (595.28, 841.89),
+            PageSize::A5 => (419.53, 595.28),
+            PageSize::A6 => (297.64, 419.53),
+            PageSize::Letter => (612.0, 792.0),
+        }
+    }
+}
+
+/// The standard PDF fonts, available without embedding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Font {
+    Regular,
+    Bold,
+}
+
+impl Font {
+    fn resource(&self) -> &'static str {
+        match self {
+            Font::Regular => "/F1",
+            Font::Bold => "/F2",
+        }
+    }
+}
+
+/// A page of a PDF document.
+///
+/// The origin is the bottom left corner of the page.
+pub struct Page {
+    width: f32,
+    height: f32,
+    content: Vec<u8>,
+}
+
+impl Page {
+    pub fn new(size: PageSize) -> Self {
+        let (width, height) = size.dimensions();
+        Self {
+            width,
+            height,
+            content: Vec::new(),
+        }
+    }
+
+    pub fn width(&self) -> f32 {
+        self.width
+    }
+
+    pub fn height(&self) -> f32 {
+        self.height
+    }
+
+    /// Draw the outline of a rectangle with a thin gray line.
+    pub fn stroke_rect(&mut self, x: f32, y: f32, width: f32, height: f32) {
+        writeln!(
+            self.content,
+            "q 0.7 G 0.5 w {x:.2} {y:.2} {width:.2} {height:.2} re S Q"
+        )
+        .unwrap();
+    }
+
+    /// Write a line of text, starting at `x`.
+    pub fn text(&mut self, font: Font, size: f32, x: f32, y: f32, text: &str) {
+        write!(
+            self.content,
+            "BT {} {size:.2} Tf {x:.2} {y:.2} Td (",
+            font.resource()
+        )
+        .unwrap();
+        self.content.extend(encode_text(text));
+        writeln!(self.content, ") Tj ET").unwrap();
+    }
+
+    /// Write a line of text, centered on `center_x`.
+    pub fn text_centered(&mut self, font: Font, size: f32, center_x: f32, y: f32, text: &str) {
+        let x = center_x - text_width(text, size) / 2.0;
+        self.text(font, size, x, y, text);
+    }
+
+    /// Draw a QR code, `x` and `y` being its bottom left corner.
+    pub fn qr_code(&mut self, image: &QrImage, x: f32, y: f32, size: f32) {
+        let module = size / image.full_width() as f32;
+        // The QR code origin is the top left corner
+        let top = y + size;
+        writeln!(self.content, "q 0 g").unwrap();
+        for (mx, my) in image.dark_modules() {
+            writeln!(
+                self.content,
+                "{:.3} {:.3} {:.3} {:.3} re",
+                x + mx as f32 * module,
+                top - (my + 1) as f32 * module,
+                module,
+                module
+            )
+            .unwrap();
+        }
+        writeln!(self.content, "f Q").unwrap();
+    }
+}
+
+/// A PDF document, using only the standard Helvetica fonts.
+#[derive(Default)]
+pub struct Document {
+    pages: Vec<Page>,
+}
+
+impl Document {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_page(&mut self, page: Page) {
+        self.pages.push(page);
+    }
+
+    /// Serialize the document.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        // Objects: 1 catalog, 2 pages, 3 and 4 fonts, then a page and its content for each page
+        let mut objects: Vec<Vec<u8>> = Vec::new();
+
+        let kids: Vec<String> = (0..self.pages.len())
+            .map(|i| format!("{} 0 R", 5 + 2 * i))
+            .collect();
+
+        objects.push(b"<< /Type /Catalog /Pages 2 0 R >>".to_vec());
+        objects.push(
+            format!(
+                "<< /Type /Pages /Kids [{}] /Count {} >>",
+                kids.join(" "),
+                self.pages.len()
+            )
+            .into_bytes(),
+        );
+        for font in ["Helvetica", "Helvetica-Bold"] {
+            objects.push(
+                format!(
+                    "<< /Type /Font /Subtype /Type1 /BaseFont /{font} /Encoding /WinAnsiEncoding >>"
+                )
+                .into_bytes(),
+            );
+        }
+
+        for (i, page) in self.pages.iter().enumerate() {
+            objects.push(
+                format!(
+                    concat!(
+                        "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] ",
+                        "/Resources << /Font << /F1 3 0 R /F2 4 0 R >> >> /Contents {} 0 R >>"
+                    ),
+                    page.width,
+                    page.height,
+                    6 + 2 * i
+                )
+                .into_bytes(),
+            );
+
+            let mut content =
+                format!("<< /Length {} >>\nstream\n", page.content.len()).into_bytes();
+            content.extend(&page.content);
+            content.extend(b"\nendstream");
+            objects.push(content);
+        }
+
+        let mut pdf = b"%PDF-1.4\n".to_vec();
+        let mut offsets = Vec::with_capacity(objects.len());
+        for (i, object) in objects.iter().enumerate() {
+            offsets.push(pdf.len());
+            writeln!(pdf, "{} 0 obj", i + 1).unwrap();
+            pdf.extend(object);
+            pdf.extend(b"\nendobj\n");
+        }
+
+        let xref = pdf.len();
+        write!(pdf, "xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).unwrap();
+        for offset in offsets {
+            writeln!(pdf, "{offset:010} 00000 n ").unwrap();
+        }
+        write!(
+            pdf,
+            "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{xref}\n%%EOF\n",
+            objects.len() + 1
+        )
+        .unwrap();
+
+        pdf
+    }
+}
+
+/// Encode the text in WinAnsi (Latin-1 for the most part) and escape it for a PDF string.
+///
+/// The characters that can't be encoded are replaced by `?`.
+fn encode_text(text: &str) -> Vec<u8> {
+    let mut encoded = Vec::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '(' | ')' | '\\' => encoded.extend([b'\\', c as u8]),
+            ' '..='~' | '\u{a0}'..='\u{ff}' => encoded.push(c as u32 as u8),
+            _ => encoded.push(b'?'),
+        }
+    }
+    encoded
+}
+
+/// An estimation of the width of the text in Helvetica.
+pub fn text_width(text: &str, size: f32) -> f32 {
+    let units: u32 = text.chars().map(char_width).sum();
+    units as f32 * size / 1000.0
+}
+
+/// The width of the Helvetica characters, in thousandths of the font size.
+fn char_width(c: char) -> u32 {
+    match c {
+        'i' | 'j' | 'l' => 222,
+        ' ' | '!' | ',' | '.' | '/' | ':' | ';' | 'I' | '[' | '\\' | ']' | 'f' | 't' => 278,
+        '(' | ')' | '-' | '`' | 'r' => 333,
+        '"' => 355,
+        'J' | 'c' | 'k' | 's' | 'v' | 'x' | 'y' | 'z' => 500,
+        'F' | 'T' | 'Z' => 611,
+        'A' | 'B' | 'E' | 'K' | 'P' | 'S' | 'V' | 'X' | 'Y' | '&' => 667,
+        'C' | 'D' | 'H' | 'N' | 'R' | 'U' | 'w' => 722,
+        'G' | 'O' | 'Q' => 778,
+        'M' | 'm' => 833,
+        '%' => 889,
+        'W' => 944,
+        '@' => 1015,
+        _ => 556,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_text() {
+        assert_eq!(encode_text("abc"), b"abc");
+        assert_eq!(encode_text("a(b)c\\"), b"a\\(b\\)c\\\\");
+        assert_eq!(encode_text("é"), [0xe9]);
+        assert_eq!(encode_text("🎵"), b"?");
+    }
+
+    #[test]
+    fn test_xref_offset() {
+        let mut document = Document::new();
+        let mut page = Page::new(PageSize::A6);
+        page.text(Font::Bold, 12.0, 10.0, 10.0, "Hello");
+        document.add_page(page);
+
+        let pdf = document.to_bytes();
+        let pdf = String::from_utf8(pdf).unwrap();
+        assert!(pdf.starts_with("%PDF-1.4\n"));
+        assert!(pdf.ends_with("%%EOF\n"));
+
+        let startxref = pdf.rfind("startxref\n").unwrap() + "startxref\n".len();
+        let offset: usize = pdf[startxref..].lines().next().unwrap().parse().unwrap();
+        assert!(pdf[offset..].starts_with("xref\n0 7\n"));
+    }
+}
@@ -0,0 +1,72 @@
+/// The words refused in the messages, in English and French.
+const PROFANITIES: &[&str] = &[
+    "asshole",
+    "bastard",
+    "bitch",
+    "bullshit",
+    "cunt",
+    "dick",
+    "fag",
+    "faggot",
+    "fuck",
+    "fucker",
+    "fucking",
+    "motherfucker",
+    "nigger",
+    "pussy",
+    "retard",
+    "shit",
+    "slut",
+    "whore",
+    "batard",
+    "connard",
+    "connasse",
+    "couille",
+    "encule",
+    "enculé",
+    "fdp",
+    "merde",
+    "nique",
+    "pd",
+    "pute",
+    "salope",
+    "tapette",
+];
+
+/// Check if the text contains a profanity, ignoring the case and the usual letter substitutions.
+pub fn contains_profanity(text: &str) -> bool {
+    let normalized: String = text
+        .to_lowercase()
+        .chars()
+        .map(|c| match c {
+            '0' => 'o',
+            '1' => 'i',
+            '3' => 'e',
+            '4' | '@' => 'a',
+            '5' | '$' => 's',
+            '7' => 't',
+            c => c,
+        })
+        .collect();
+
+    normalized
+        .split(|c: char| !c.is_alphanumeric())
+        .any(|word| PROFANITIES.contains(&word))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_contains_profanity() {
+        assert!(contains_profanity("Play this shit!"));
+        assert!(contains_profanity("What the FUCK"));
+        assert!(contains_profanity("sh1t"));
+        assert!(contains_profanity("quelle merde"));
+        assert!(!contains_profanity("For Julie's birthday!"));
+        // Only whole words are refused
+        assert!(!contains_profanity("Dickens, Scunthorpe and Shiitake"));
+        assert!(!contains_profanity(""));
+    }
+}
@@ -0,0 +1,226 @@
+use std::fmt::Write;
+
+use qrcode::{types::QrError, Color, EcLevel, QrCode};
+use serde::Deserialize;
+
+/// The error correction level of a QR code.
+///
+/// The higher the level, the more the code can be damaged (or covered by a logo)
+/// and still be readable, but the bigger the code is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+pub enum ErrorCorrection {
+    /// Recovers 7% of the data
+    L,
+    /// Recovers 15% of the data
+    #[default]
+    M,
+    /// Recovers 25% of the data
+    Q,
+    /// Recovers 30% of the data
+    H,
+}
+
+impl From<ErrorCorrection> for EcLevel {
+    fn from(ec: ErrorCorrection) -> Self {
+        match ec {
+            ErrorCorrection::L => EcLevel::L,
+            ErrorCorrection::M => EcLevel::M,
+            ErrorCorrection::Q => EcLevel::Q,
+            ErrorCorrection::H => EcLevel::H,
+        }
+    }
+}
+
+/// The rendering options of a QR code.
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct QrOptions {
+    /// The maximum width of the image in pixels.
+    ///
+    /// The image is smaller as the modules are a whole number of pixels,
+    /// but never less than one pixel per module.
+    #[serde(default = "QrOptions::default_size")]
+    pub size: u32,
+    /// The width of the quiet zone around the code, in modules.
+    #[serde(default = "QrOptions::default_margin")]
+    pub margin: u32,
+    #[serde(default)]
+    pub ec: ErrorCorrection,
+}
+
+impl QrOptions {
+    pub const MAX_SIZE: u32 = 1024;
+    pub const MAX_MARGIN: u32 = 32;
+
+    fn default_size() -> u32 {
+        512
+    }
+
+    fn default_margin() -> u32 {
+        4
+    }
+}
+
+impl Default for QrOptions {
+    fn default() -> Self {
+        Self {
+            size: Self::default_size(),
+            margin: Self::default_margin(),
+            ec: ErrorCorrection::default(),
+        }
+    }
+}
+
+/// A QR code ready to be rendered.
+pub struct QrImage {
+    modules: Vec<Color>,
+    width: u32,
+    options: QrOptions,
+}
+
+impl QrImage {
+    /// Encode the data in a QR code.
+    pub fn new(data: &str, options: QrOptions) -> Result<Self, QrError> {
+        let code = QrCode::with_error_correction_level(data, options.ec.into())?;
+        Ok(Self {
+            width: code.width() as u32,
+            modules: code.into_colors(),
+            options,
+        })
+    }
+
+    /// The width of the code with its quiet zone, in modules.
+    pub fn full_width(&self) -> u32 {
+        self.width + 2 * self.options.margin
+    }
+
+    /// The size of a module in pixels.
+    fn scale(&self) -> u32 {
+        (self.options.size / self.full_width()).max(1)
+    }
+
+    fn is_dark(&self, x: u32, y: u32) -> bool {
+        self.modules[(y * self.width + x) as usize] == Color::Dark
+    }
+
+    /// The position of the dark modules, including the quiet zone offset.
+    ///
+    /// The origin is the top left corner.
+    pub fn dark_modules(&self) -> impl Iterator<Item = (u32, u32)> + '_ {
+        let margin = self.options.margin;
+        (0..self.width)
+            .flat_map(move |y| (0..self.width).map(move |x| (x, y)))
+            .filter(|&(x, y)| self.is_dark(x, y))
+            .map(move |(x, y)| (x + margin, y + margin))
+    }
+
+    /// Render the QR code as an SVG image.
+    pub fn to_svg(&self) -> String {
+        let full_width = self.full_width();
+        let pixels = full_width * self.scale();
+
+        let mut path = String::new();
+        for (x, y) in self.dark_modules() {
+            write!(path, "M{},{}h1v1h-1z", x, y).unwrap();
+        }
+
+        format!(
+            concat!(
+                r#"<?xml version="1.0" encoding="UTF-8"?>"#,
+                r#"<svg xmlns="http://www.w3.org/2000/svg" version="1.1" "#,
+                r#"width="{pixels}" height="{pixels}" viewBox="0 0 {full} {full}" "#,
+                r#"shape-rendering="crispEdges">"#,
+                r##"<rect width="100%" height="100%" fill="#fff"/>"##,
+                r##"<path fill="#000" d="{path}"/>"##,
+                "</svg>"
+            ),
+            pixels = pixels,
+            full = full_width,
+            path = path,
+        )
+    }
+
+    /// Render the QR code as a grayscale PNG image.
+    pub fn to_png(&self) -> Result<Vec<u8>, png::EncodingError> {
+        let scale = self.scale();
+        let margin = self.options.margin;
+        let pixels = self.full_width() * scale;
+
+        let mut data = vec![u8::MAX; (pixels * pixels) as usize];
+        for py in 0..pixels {
+            for px in 0..pixels {
+                let (x, y) = (px / scale, py / scale);
+                let inside = (margin..margin + self.width).contains(&x)
+                    && (margin..margin + self.width).contains(&y);
+                if inside && self.is_dark(x - margin, y - margin) {
+                    data[(py * pixels + px) as usize] = 0;
+                }
+            }
+        }
+
+        let mut image = Vec::new();
+        let mut encoder = png::Encoder::new(&mut image, pixels, pixels);
+        encoder.set_color(png::ColorType::Grayscale);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&data)?;
+        writer.finish()?;
+
+        Ok(image)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const URL: &str = "https://dj-store.example/j/ABCDEF";
+
+    #[test]
+    fn test_svg_size() {
+        let options = QrOptions {
+            size: 300,
+            margin: 2,
+            ec: ErrorCorrection::L,
+        };
+        let image = QrImage::new(URL, options).unwrap();
+        let full_width = image.full_width();
+        let pixels = full_width * image.scale();
+
+        let svg = image.to_svg();
+        assert!(pixels <= 300);
+        assert!(svg.contains(&format!(r#"width="{pixels}""#)));
+        assert!(svg.contains(&format!(r#"viewBox="0 0 {full_width} {full_width}""#)));
+    }
+
+    #[test]
+    fn test_png_header() {
+        let image = QrImage::new(URL, QrOptions::default()).unwrap();
+        let png = image.to_png().unwrap();
+        assert_eq!(&png[1..4], b"PNG");
+    }
+
+    #[test]
+    fn test_higher_error_correction_is_bigger() {
+        let low = QrOptions {
+            ec: ErrorCorrection::L,
+            ..Default::default()
+        };
+        let high = QrOptions {
+            ec: ErrorCorrection::H,
+            ..Default::default()
+        };
+        let low = QrImage::new(URL, low).unwrap();
+        let high = QrImage::new(URL, high).unwrap();
+        assert!(high.width > low.width);
+    }
+
+    #[test]
+    fn test_small_size_keeps_one_pixel_modules() {
+        let options = QrOptions {
+            size: 1,
+            ..Default::default()
+        };
+        let image = QrImage::new(URL, options).unwrap();
+        assert_eq!(image.scale(), 1);
+    }
+}
@@ -0,0 +1,217 @@
+use std::cmp::Ordering;
+
+use chrono::{DateTime, Duration, Utc};
+use entity::{room_settings::RankingAlgorithm, vote::VoteValue};
+
+/// The last vote of a user for a music.
+#[derive(Debug, Clone, Copy)]
+pub struct RankedVote {
+    pub value: VoteValue,
+    pub date: DateTime<Utc>,
+}
+
+impl RankedVote {
+    fn weight(&self) -> f64 {
+        match self.value {
+            VoteValue::Like => 1.0,
+            VoteValue::Neutral => 0.0,
+            VoteValue::Dislike => -1.0,
+        }
+    }
+}
+
+/// A way to score a music from its votes, the highest score being ranked first.
+pub trait Ranking {
+    fn score(&self, votes: &[RankedVote], now: DateTime<Utc>) -> f64;
+
+    /// Sort the items by decreasing score, keeping the order of the items with the same score.
+    fn rank<T>(&self, items: Vec<(T, Vec<RankedVote>)>, now: DateTime<Utc>) -> Vec<(T, f64)> {
+        let mut ranked: Vec<(T, f64)> = items
+            .into_iter()
+            .map(|(item, votes)| {
+                let score = self.score(&votes, now);
+                (item, score)
+            })
+            .collect();
+        ranked.sort_by(|(_, a), (_, b)| b.partial_cmp(a).unwrap_or(Ordering::Equal));
+        ranked
+    }
+}
+
+/// The z-score of the 95% confidence level.
+const WILSON_Z: f64 = 1.96;
+/// The time for a vote to lose half of its weight in the hot score.
+const HOT_HALF_LIFE_MINUTES: f64 = 30.0;
+/// The period of the votes counted in the trending score.
+const TRENDING_WINDOW_MINUTES: i64 = 15;
+
+impl Ranking for RankingAlgorithm {
+    fn score(&self, votes: &[RankedVote], now: DateTime<Utc>) -> f64 {
+        match self {
+            RankingAlgorithm::Sum => votes.iter().map(RankedVote::weight).sum(),
+            RankingAlgorithm::Wilson => wilson_lower_bound(votes),
+            RankingAlgorithm::Hot => votes
+                .iter()
+                .map(|vote| {
+                    let age = minutes_between(vote.date, now);
+                    vote.weight() * 0.5f64.powf(age / HOT_HALF_LIFE_MINUTES)
+                })
+                .sum(),
+            // Net votes per minute over the window
+            RankingAlgorithm::Trending => {
+                let start = now - Duration::minutes(TRENDING_WINDOW_MINUTES);
+                let recent: f64 = votes
+                    .iter()
+                    .filter(|vote| vote.date > start)
+                    .map(RankedVote::weight)
+                    .sum();
+                recent / TRENDING_WINDOW_MINUTES as f64
+            }
+        }
+    }
+}
+
+/// The lower bound of the Wilson score interval of the likes ratio.
+///
+/// A music with few votes gets a low score until there is enough votes to trust its ratio.
+fn wilson_lower_bound(votes: &[RankedVote]) -> f64 {
+    let likes = votes.iter().filter(|v| v.value == VoteValue::Like).count() as f64;
+    let dislikes = votes
+        .iter()
+        .filter(|v| v.value == VoteValue::Dislike)
+        .count() as f64;
+    let n = likes + dislikes;
+    if n == 0.0 {
+        return 0.0;
+    }
+
+    let z2 = WILSON_Z * WILSON_Z;
+    let p = likes / n;
+    let center = p + z2 / (2.0 * n);
+    let margin = WILSON_Z * (p * (1.0 - p) / n + z2 / (4.0 * n * n)).sqrt();
+    (center - margin) / (1.0 + z2 / n)
+}
+
+/// The minutes elapsed since `date`, a date in the future counting as now.
+fn minutes_between(date: DateTime<Utc>, now: DateTime<Utc>) -> f64 {
+    (now - date).num_seconds().max(0) as f64 / 60.0
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn now() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2023, 6, 1, 23, 0, 0).unwrap()
+    }
+
+    /// Build votes cast `minutes_ago`.
+    fn votes(value: VoteValue, count: usize, minutes_ago: i64) -> Vec<RankedVote> {
+        let date = now() - Duration::minutes(minutes_ago);
+        vec![RankedVote { value, date }; count]
+    }
+
+    fn ranking(algorithm: RankingAlgorithm, items: Vec<(&str, Vec<RankedVote>)>) -> Vec<&str> {
+        algorithm
+            .rank(items, now())
+            .into_iter()
+            .map(|(item, _)| item)
+            .collect()
+    }
+
+    #[test]
+    fn test_sum() {
+        let votes = [
+            votes(VoteValue::Like, 5, 10),
+            votes(VoteValue::Dislike, 2, 10),
+            votes(VoteValue::Neutral, 3, 10),
+        ]
+        .concat();
+        assert_eq!(RankingAlgorithm::Sum.score(&votes, now()), 3.0);
+        assert_eq!(RankingAlgorithm::Sum.score(&[], now()), 0.0);
+    }
+
+    #[test]
+    fn test_wilson_prefers_confidence() {
+        let items = vec![
+            ("one like", votes(VoteValue::Like, 1, 0)),
+            (
+                "mostly liked",
+                [
+                    votes(VoteValue::Like, 90, 0),
+                    votes(VoteValue::Dislike, 10, 0),
+                ]
+                .concat(),
+            ),
+            (
+                "controversial",
+                [
+                    votes(VoteValue::Like, 50, 0),
+                    votes(VoteValue::Dislike, 50, 0),
+                ]
+                .concat(),
+            ),
+        ];
+        assert_eq!(
+            ranking(RankingAlgorithm::Wilson, items),
+            ["mostly liked", "controversial", "one like"]
+        );
+    }
+
+    #[test]
+    fn test_wilson_bounds() {
+        let all_likes = votes(VoteValue::Like, 1000, 0);
+        let all_dislikes = votes(VoteValue::Dislike, 1000, 0);
+        let score = RankingAlgorithm::Wilson.score(&all_likes, now());
+        assert!(score > 0.99 && score < 1.0);
+        assert!(RankingAlgorithm::Wilson.score(&all_dislikes, now()) < 0.01);
+        assert_eq!(RankingAlgorithm::Wilson.score(&[], now()), 0.0);
+    }
+
+    #[test]
+    fn test_hot_decay() {
+        let fresh = votes(VoteValue::Like, 1, 0);
+        let half_life = votes(VoteValue::Like, 1, 30);
+        assert_eq!(RankingAlgorithm::Hot.score(&fresh, now()), 1.0);
+        assert_eq!(RankingAlgorithm::Hot.score(&half_life, now()), 0.5);
+
+        let items = vec![
+            ("early requests", votes(VoteValue::Like, 10, 180)),
+            ("recent requests", votes(VoteValue::Like, 3, 5)),
+        ];
+        assert_eq!(
+            ranking(RankingAlgorithm::Hot, items.clone()),
+            ["recent requests", "early requests"]
+        );
+        assert_eq!(
+            ranking(RankingAlgorithm::Sum, items),
+            ["early requests", "recent requests"]
+        );
+    }
+
+    #[test]
+    fn test_trending_window() {
+        let votes = [
+            votes(VoteValue::Like, 6, 5),
+            votes(VoteValue::Dislike, 3, 10),
+            votes(VoteValue::Like, 100, 20),
+        ]
+        .concat();
+        assert_eq!(RankingAlgorithm::Trending.score(&votes, now()), 0.2);
+    }
+
+    #[test]
+    fn test_rank_keeps_order_of_ties() {
+        let items = vec![
+            ("first", votes(VoteValue::Like, 1, 0)),
+            ("second", votes(VoteValue::Like, 1, 0)),
+            ("third", votes(VoteValue::Like, 2, 0)),
+        ];
+        assert_eq!(
+            ranking(RankingAlgorithm::Sum, items),
+            ["third", "first", "second"]
+        );
+    }
+}
@@ -0,0 +1,92 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    hash::Hash,
+    time::{Duration, Instant},
+};
+
+/// Limit the number of actions of each key over a sliding window.
+pub struct RateLimiter<K> {
+    max_actions: usize,
+    window: Duration,
+    actions: HashMap<K, VecDeque<Instant>>,
+}
+
+impl<K: Eq + Hash> RateLimiter<K> {
+    pub fn new(max_actions: usize, window: Duration) -> Self {
+        Self {
+            max_actions,
+            window,
+            actions: HashMap::new(),
+        }
+    }
+
+    /// Check if the key reached its limit, without recording an action.
+    pub fn is_limited(&mut self, key: &K, now: Instant) -> bool {
+        let window = self.window;
+        match self.actions.get_mut(key) {
+            Some(actions) => {
+                forget_old(actions, now, window);
+                actions.len() >= self.max_actions
+            }
+            None => false,
+        }
+    }
+
+    /// Check if the key can act now, recording the action if it can.
+    pub fn try_acquire(&mut self, key: K, now: Instant) -> bool {
+        let window = self.window;
+        let actions = self.actions.entry(key).or_default();
+        forget_old(actions, now, window);
+
+        if actions.len() >= self.max_actions {
+            return false;
+        }
+        actions.push_back(now);
+        true
+    }
+
+    /// Forget the keys without action in the window.
+    pub fn sweep(&mut self, now: Instant) {
+        let window = self.window;
+        self.actions.retain(|_, actions| {
+            actions
+                .back()
+                .is_some_and(|&date| now.duration_since(date) < window)
+        });
+    }
+}
+
+/// Remove the actions out of the window.
+fn forget_old(actions: &mut VecDeque<Instant>, now: Instant, window: Duration) {
+    while actions
+        .front()
+        .is_some_and(|&date| now.duration_since(date) >= window)
+    {
+        actions.pop_front();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sliding_window() {
+        let start = Instant::now();
+        let mut limiter = RateLimiter::new(2, Duration::from_secs(10));
+
+        assert!(limiter.try_acquire("a", start));
+        assert!(limiter.try_acquire("a", start + Duration::from_secs(5)));
+        assert!(!limiter.try_acquire("a", start + Duration::from_secs(6)));
+        assert!(limiter.is_limited(&"a", start + Duration::from_secs(6)));
+        assert!(!limiter.is_limited(&"c", start + Duration::from_secs(6)));
+        // The keys are limited separately
+        assert!(limiter.try_acquire("b", start + Duration::from_secs(6)));
+        // The first action left the window
+        assert!(limiter.try_acquire("a", start + Duration::from_secs(10)));
+        assert!(!limiter.try_acquire("a", start + Duration::from_secs(11)));
+
+        limiter.sweep(start + Duration::from_secs(20));
+        assert!(limiter.actions.is_empty());
+    }
+}
@@ -27,7 +27,7 @@ pub struct RoomID {
 
 impl RoomID {
     /// Create a new room ID from a number.
-    pub fn new(value: u32) -> Self {
+    pub const fn new(value: u32) -> Self {
         Self { value }
     }
 
@@ -0,0 +1,65 @@
+use std::{collections::VecDeque, sync::Arc, time::Duration};
+
+use tokio::{
+    sync::Mutex,
+    time::{sleep_until, Instant},
+};
+
+/// Allow at most a number of calls over a sliding window, the callers waiting their turn.
+#[derive(Clone)]
+pub struct Throttle {
+    max_calls: usize,
+    window: Duration,
+    calls: Arc<Mutex<VecDeque<Instant>>>,
+}
+
+impl Throttle {
+    pub fn new(max_calls: usize, window: Duration) -> Self {
+        Self {
+            max_calls,
+            window,
+            calls: Arc::new(Mutex::new(VecDeque::with_capacity(max_calls))),
+        }
+    }
+
+    /// Wait until a call is allowed, and record it.
+    pub async fn acquire(&self) {
+        // The lock is held while waiting, so the callers are served in order
+        let mut calls = self.calls.lock().await;
+
+        let now = Instant::now();
+        while calls
+            .front()
+            .is_some_and(|&date| now.duration_since(date) >= self.window)
+        {
+            calls.pop_front();
+        }
+
+        if calls.len() >= self.max_calls {
+            if let Some(oldest) = calls.pop_front() {
+                sleep_until(oldest + self.window).await;
+            }
+        }
+        calls.push_back(Instant::now());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_throttle() {
+        let window = Duration::from_millis(200);
+        let throttle = Throttle::new(2, window);
+
+        let start = Instant::now();
+        throttle.acquire().await;
+        throttle.acquire().await;
+        assert!(start.elapsed() < window);
+
+        // The third call waits for the first one to leave the window
+        throttle.acquire().await;
+        assert!(start.elapsed() >= window);
+    }
+}
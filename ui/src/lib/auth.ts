@@ -48,13 +48,14 @@ async function connect(username: string, password: string) {
 	auth.set({ access_token, role: 'Admin' });
 }
 
-async function joinRoom(room_id: RoomId) {
+async function joinRoom(room_id: RoomId, nickname?: string) {
 	// If the user is already in the room or is an admin, do nothing
 	const current_auth = get(auth);
 	if (current_auth?.room_id === room_id || current_auth?.role === 'Admin') return;
 
 	disconnect();
-	const res = await fetch(`${env.API_URL}/api/room/${room_id}/join`);
+	const query = nickname ? `?nickname=${encodeURIComponent(nickname)}` : '';
+	const res = await fetch(`${env.API_URL}/api/room/${room_id}/join${query}`);
 
 	if (!res.ok) {
 		const message = res.statusText;
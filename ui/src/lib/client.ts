@@ -1,23 +1,35 @@
 import { error } from '@sveltejs/kit';
 import { writable, type Writable } from 'svelte/store';
-import type { Music, MusicId, Room, RoomId, Vote } from './types';
+import type { Music, MusicId, RankingPage, Room, RoomId, Vote } from './types';
 import { convertApiRoom, env } from './utils';
 
 const voted_for: Writable<Set<MusicId>> = writable(new Set());
 
 async function getMusics(auth_token: string, room_id: RoomId): Promise<Music[]> {
-	const res = await fetch(`${env.API_URL}/api/room/${room_id}/music/all`, {
-		headers: {
-			Authorization: `Bearer ${auth_token}`
+	const musics: Music[] = [];
+	let cursor: string | undefined;
+	do {
+		const params = new URLSearchParams({ limit: '500' });
+		if (cursor) {
+			params.set('cursor', cursor);
+		}
+		const res = await fetch(`${env.API_URL}/api/room/${room_id}/music/all?${params}`, {
+			headers: {
+				Authorization: `Bearer ${auth_token}`
+			}
+		});
+		if (!res.ok) {
+			const message = res.statusText;
+			const detail = await res.text();
+			throw error(res.status, { message, detail });
 		}
-	});
-	if (!res.ok) {
-		const message = res.statusText;
-		const detail = await res.text();
-		throw error(res.status, { message, detail });
-	}
 
-	return res.json();
+		const page: RankingPage = await res.json();
+		musics.push(...page.musics);
+		cursor = page.next_cursor;
+	} while (cursor);
+
+	return musics;
 }
 
 async function getSearch(auth_token: string, rooom_id: RoomId, query: string): Promise<Music[]> {
@@ -61,6 +73,32 @@ async function voteForMusic(auth_token: string, room_id: RoomId, like: boolean,
 	});
 }
 
+async function retractVote(auth_token: string, room_id: RoomId, music_id: MusicId) {
+	const response = await fetch(`${env.API_URL}/api/room/${room_id}/vote/${music_id}`, {
+		method: 'DELETE',
+		headers: {
+			Authorization: `Bearer ${auth_token}`
+		}
+	});
+
+	if (!response.ok) {
+		throw new Error(`Error retracting vote: ${await response.text()}`);
+	}
+	voted_for.update((set) => {
+		set.delete(music_id);
+		return set;
+	});
+}
+
+async function sendHeartbeat(auth_token: string, room_id: RoomId): Promise<void> {
+	await fetch(`${env.API_URL}/api/room/${room_id}/heartbeat`, {
+		method: 'POST',
+		headers: {
+			Authorization: `Bearer ${auth_token}`
+		}
+	});
+}
+
 async function getRooms(auth_token: string): Promise<Room[]> {
 	const res = await fetch(`${env.API_URL}/api/room/all`, {
 		headers: {
@@ -133,7 +171,7 @@ async function getVotes(access_token: string, room_id: RoomId): Promise<void> {
 
 	const votes: Vote[] = await res.json();
 
-	const new_votes = new Set(votes.filter((v) => v.like).map((v) => v.music_id));
+	const new_votes = new Set(votes.filter((v) => v.value === 'like').map((v) => v.music_id));
 	voted_for.set(new_votes);
 }
 
@@ -141,6 +179,8 @@ export {
 	getMusics,
 	getSearch,
 	voteForMusic,
+	retractVote,
+	sendHeartbeat,
 	getRooms,
 	deleteRoom,
 	createRoom,
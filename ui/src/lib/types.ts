@@ -7,21 +7,37 @@ export type Music = {
 	artist: string;
 	preview_url?: string;
 	image_hash?: string;
+	/** Net score, likes minus dislikes */
 	votes: number;
+	likes: number;
+	dislikes: number;
+	status: RequestStatus;
+	reason?: string;
 };
 
+export type RankingPage = {
+	musics: Music[];
+	total: number;
+	next_cursor?: string;
+};
+
+export type RequestStatus = 'pending' | 'accepted' | 'rejected' | 'played';
+
+export type VoteValue = 'like' | 'dislike' | 'neutral';
+
 export type Vote = {
 	music_id: MusicId;
 	title: string;
 	artist: string;
 	vote_date: Date;
-	like: boolean;
+	value: VoteValue;
 };
 
 export type Room = {
 	id: RoomId;
 	creation: Date;
 	expiration: Date;
-	user_count: number;
+	join_count: number;
+	active_users: number;
 	active: boolean;
 };